@@ -0,0 +1,102 @@
+use namada_sdk::types::address::Address;
+
+use crate::database::Database;
+use crate::Error;
+
+pub struct Account {
+    pub address: String,
+    pub account_type: String,
+    // Left unchanged when empty
+    pub public_keys: Vec<String>,
+    pub threshold: Option<i16>,
+    pub vp_code_hash: Option<String>,
+    pub height: i64,
+}
+
+impl Account {
+    pub fn new(
+        address: &Address,
+        public_keys: Vec<String>,
+        threshold: Option<u8>,
+        vp_code_hash: Option<String>,
+        height: i64,
+    ) -> Self {
+        Account {
+            address: address.encode(),
+            account_type: match address {
+                Address::Established(_) => "established".to_string(),
+                Address::Implicit(_) => "implicit".to_string(),
+                Address::Internal(_) => "internal".to_string(),
+            },
+            public_keys,
+            threshold: threshold.map(|t| t as i16),
+            vp_code_hash,
+            height,
+        }
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO account (address, type, public_keys, threshold, vp_code_hash, creation_height, last_update_height)
+            VALUES ($1, $2, $3, $4, $5, $6, $6)
+            ON CONFLICT (address) DO UPDATE
+                SET public_keys = CASE WHEN cardinality(EXCLUDED.public_keys) > 0
+                        THEN EXCLUDED.public_keys ELSE account.public_keys END,
+                    threshold = COALESCE(EXCLUDED.threshold, account.threshold),
+                    vp_code_hash = COALESCE(EXCLUDED.vp_code_hash, account.vp_code_hash),
+                    creation_height = LEAST(account.creation_height, EXCLUDED.creation_height),
+                    last_update_height = EXCLUDED.last_update_height
+            WHERE account.last_update_height <= EXCLUDED.last_update_height
+            "#,
+        )
+        .bind(&self.address)
+        .bind(&self.account_type)
+        .bind(&self.public_keys)
+        .bind(self.threshold)
+        .bind(&self.vp_code_hash)
+        .bind(self.height)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct RevealedPk {
+    pub public_key: String,
+    pub address: String,
+    pub tx_hash: String,
+    pub height: i64,
+}
+
+impl RevealedPk {
+    pub fn new(public_key: String, address: String, tx_hash: String, height: i64) -> Self {
+        RevealedPk {
+            public_key,
+            address,
+            tx_hash,
+            height,
+        }
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO revealed_pk (public_key, address, transaction_hash, height)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&self.public_key)
+        .bind(&self.address)
+        .bind(&self.tx_hash)
+        .bind(self.height)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use namada_sdk::ibc::apps::transfer::types::msgs::transfer::MsgTransfer;
+use namada_sdk::types::token;
+use serde_json::Value;
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::database::Database;
+use crate::utils;
+use crate::Error;
+
+pub struct TokenTransfer {
+    pub tx_hash: String,
+    pub height: i64,
+    pub source: String,
+    pub target: String,
+    pub token: String,
+    pub amount: String,
+    pub denomination: i16,
+    pub key: Option<String>,
+    pub shielded: bool,
+}
+
+impl TokenTransfer {
+    pub fn from_transfer(tx_hash: String, height: i64, transfer: &token::Transfer) -> Self {
+        TokenTransfer {
+            tx_hash,
+            height,
+            source: transfer.source.encode(),
+            target: transfer.target.encode(),
+            token: transfer.token.encode(),
+            amount: utils::raw_amount(transfer.amount.amount()),
+            denomination: u8::from(transfer.amount.denom()) as i16,
+            key: transfer.key.clone(),
+            shielded: transfer.shielded.is_some(),
+        }
+    }
+
+    // IBC amounts are already expressed in the base unit of the denom
+    pub fn from_ibc_transfer(
+        tx_hash: String,
+        height: i64,
+        transfer: &MsgTransfer,
+        shielded: bool,
+    ) -> Self {
+        TokenTransfer {
+            tx_hash,
+            height,
+            source: transfer.packet_data.sender.to_string(),
+            target: transfer.packet_data.receiver.to_string(),
+            token: transfer.packet_data.token.denom.to_string(),
+            amount: transfer.packet_data.token.amount.to_string(),
+            denomination: 0,
+            key: None,
+            shielded,
+        }
+    }
+
+    // Decodes the ICS-20 packet data of a received packet, returns None for other packets
+    pub fn from_ibc_packet(tx_hash: String, height: i64, data: &[u8]) -> Option<Self> {
+        let packet_data = serde_json::from_slice::<Value>(data).ok()?;
+
+        Some(TokenTransfer {
+            tx_hash,
+            height,
+            source: packet_data["sender"].as_str()?.to_string(),
+            target: packet_data["receiver"].as_str()?.to_string(),
+            token: packet_data["denom"].as_str()?.to_string(),
+            amount: packet_data["amount"].as_str()?.to_string(),
+            denomination: 0,
+            key: None,
+            shielded: false,
+        })
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO token_transfer (transaction_hash, height, source, target, token, amount, denomination, key, shielded)
+            VALUES ($1, $2, $3, $4, $5, $6::NUMERIC, $7, $8, $9)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&self.tx_hash)
+        .bind(self.height)
+        .bind(&self.source)
+        .bind(&self.target)
+        .bind(&self.token)
+        .bind(&self.amount)
+        .bind(self.denomination)
+        .bind(&self.key)
+        .bind(self.shielded)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct AccountBalance {
+    pub address: String,
+    pub token: String,
+    pub amount: String,
+    pub denomination: i16,
+    pub height: i64,
+}
+
+impl AccountBalance {
+    pub fn new(
+        address: String,
+        token: String,
+        amount: token::Amount,
+        denomination: u8,
+        height: u64,
+    ) -> Self {
+        AccountBalance {
+            address,
+            token,
+            amount: utils::raw_amount(amount),
+            denomination: denomination as i16,
+            height: height as i64,
+        }
+    }
+}
+
+pub struct AccountBalances(Vec<AccountBalance>);
+
+impl From<Vec<AccountBalance>> for AccountBalances {
+    fn from(balances: Vec<AccountBalance>) -> Self {
+        AccountBalances(balances)
+    }
+}
+
+impl AccountBalances {
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        // Latest balances, blocks may be processed out of order
+        let mut builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("INSERT INTO account_balance (address, token, amount, denomination, height)");
+
+        builder.push_values(self.0.iter(), |mut b, balance| {
+            b.push_bind(balance.address.clone())
+                .push_bind(balance.token.clone())
+                .push_bind(balance.amount.clone())
+                .push_unseparated("::NUMERIC")
+                .push_bind(balance.denomination)
+                .push_bind(balance.height);
+        });
+        builder.push(
+            r#"
+            ON CONFLICT (address, token) DO UPDATE
+                SET amount = EXCLUDED.amount,
+                    denomination = EXCLUDED.denomination,
+                    height = EXCLUDED.height
+            WHERE account_balance.height <= EXCLUDED.height
+            "#,
+        );
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        // History
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO account_balance_history (address, token, amount, denomination, height)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, balance| {
+            b.push_bind(balance.address.clone())
+                .push_bind(balance.token.clone())
+                .push_bind(balance.amount.clone())
+                .push_unseparated("::NUMERIC")
+                .push_bind(balance.denomination)
+                .push_bind(balance.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+
+    // Returns the tokens of which a balance has been indexed
+    pub async fn tokens(db: &Database) -> Result<Vec<String>, Error> {
+        let tokens = sqlx::query_as::<_, (String,)>("SELECT DISTINCT token FROM account_balance")
+            .fetch_all(&db.pool())
+            .await?
+            .into_iter()
+            .map(|(token,)| token)
+            .collect();
+
+        Ok(tokens)
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::NaiveDateTime;
+use namada_sdk::state::Epoch;
 use tendermint::abci::types::ExecTxResult;
 use tendermint::block::Block as TmBlock;
 use sqlx::FromRow;
@@ -7,6 +8,9 @@ use crate::database::Database;
 use crate::utils;
 use crate::Error;
 
+// Epochs before the current one whose proposed blocks are still counted once completed
+const COMPLETED_EPOCHS_LOOKBACK: u64 = 2;
+
 #[derive(FromRow)]
 pub struct Block {
     pub height: i64,
@@ -15,26 +19,31 @@ pub struct Block {
     pub total_gas: i64,
     pub proposer_address: String,
     pub timestamp: NaiveDateTime,
+    pub epoch: i64,
 }
 
 impl Block {
-    pub fn from_tm_block(block: TmBlock, tx_results: Vec<ExecTxResult>) -> Self {
-        Self {
+    pub fn from_tm_block(
+        block: TmBlock,
+        tx_results: Vec<ExecTxResult>,
+        epoch: Epoch,
+    ) -> Result<Self, Error> {
+        Ok(Self {
             height: block.header.height.into(),
             hash: block.header.hash().to_string(),
             num_txs: block.data.len() as i32,
             total_gas: sum_total_gas(tx_results),
-            proposer_address: utils::addr_to_bech32(block.header.proposer_address),
-            timestamp: NaiveDateTime::from_timestamp_opt(block.header.time.unix_timestamp(), 0)
-                .expect("invalid timestamp"),
-        }
+            proposer_address: utils::consensus_address(block.header.proposer_address),
+            timestamp: utils::timestamp(block.header.time)?.naive_utc(),
+            epoch: epoch.0 as i64,
+        })
     }
 
     pub async fn save(&self, db: &Database) -> Result<(), Error> {
         sqlx::query(
             r#"
-            INSERT INTO block (height, hash, num_txs, total_gas, proposer_address, timestamp)
-            VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT DO NOTHING"#,
+            INSERT INTO block (height, hash, num_txs, total_gas, proposer_address, timestamp, epoch)
+            VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT DO NOTHING"#,
         )
         .bind(self.height)
         .bind(self.hash.clone())
@@ -42,6 +51,7 @@ impl Block {
         .bind(self.total_gas)
         .bind(self.proposer_address.clone())
         .bind(self.timestamp)
+        .bind(self.epoch)
         .execute(&db.pool())
         .await?;
 
@@ -91,6 +101,82 @@ impl Block {
     }
 }
 
+//--------------------------------------------------------
+
+pub struct ValidatorProposedBlocks;
+
+impl ValidatorProposedBlocks {
+    // Counts the blocks proposed by each validator during the epochs before `epoch` fully
+    // indexed since the last call. The expected count is the sum over the blocks of the share
+    // of the voting power of the validator, taken from the pre-commits of the block saved at
+    // the next height. Epochs with missing blocks or pre-commits, e.g. during catch-up, are left
+    // for later calls, up to COMPLETED_EPOCHS_LOOKBACK epochs so the cost stays constant.
+    pub async fn update_completed(db: &Database, epoch: Epoch) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            WITH epochs AS (
+                SELECT epoch, MIN(height) AS first_height, MAX(height) AS last_height, COUNT(*) AS count
+                FROM block
+                WHERE epoch >= $1 AND epoch < $2
+                GROUP BY epoch
+            ),
+            completed AS (
+                SELECT epochs.epoch
+                FROM epochs
+                WHERE epochs.count = epochs.last_height - epochs.first_height + 1
+                  AND (epochs.first_height = 1
+                    OR EXISTS (SELECT 1 FROM block WHERE height = epochs.first_height - 1))
+                  AND EXISTS (SELECT 1 FROM block WHERE height = epochs.last_height + 1)
+                  AND (
+                    SELECT COUNT(DISTINCT height)
+                    FROM pre_commit
+                    WHERE height > epochs.first_height AND height <= epochs.last_height + 1
+                  ) = epochs.count
+                  AND NOT EXISTS (SELECT 1 FROM validator_proposed_blocks WHERE epoch = epochs.epoch)
+            ),
+            expected AS (
+                SELECT epoch,
+                       consensus_address,
+                       COALESCE(SUM(voting_power::NUMERIC / NULLIF(total_voting_power, 0)), 0) AS expected_count
+                FROM (
+                    SELECT block.epoch,
+                           pre_commit.consensus_address,
+                           pre_commit.voting_power,
+                           SUM(pre_commit.voting_power) OVER (PARTITION BY pre_commit.height) AS total_voting_power
+                    FROM block
+                    JOIN completed ON completed.epoch = block.epoch
+                    JOIN pre_commit ON pre_commit.height = block.height + 1
+                ) shares
+                GROUP BY epoch, consensus_address
+            ),
+            proposed AS (
+                SELECT block.epoch, block.proposer_address, COUNT(*) AS count
+                FROM block
+                JOIN completed ON completed.epoch = block.epoch
+                GROUP BY block.epoch, block.proposer_address
+            )
+            INSERT INTO validator_proposed_blocks (consensus_address, validator_address, epoch, count, expected_count)
+            SELECT expected.consensus_address,
+                   validator.validator_address,
+                   expected.epoch,
+                   COALESCE(proposed.count, 0),
+                   expected.expected_count
+            FROM expected
+            LEFT JOIN proposed ON proposed.epoch = expected.epoch
+                AND proposed.proposer_address = expected.consensus_address
+            LEFT JOIN validator ON validator.consensus_address = expected.consensus_address
+            ON CONFLICT (consensus_address, epoch) DO NOTHING
+            "#,
+        )
+        .bind(epoch.0.saturating_sub(COMPLETED_EPOCHS_LOOKBACK) as i64)
+        .bind(epoch.0 as i64)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
+
 fn sum_total_gas(tx_results: Vec<ExecTxResult>) -> i64 {
     tx_results.iter().map(|tx_result| tx_result.gas_used).sum()
 }
@@ -1,6 +1,7 @@
 use sqlx::types::Decimal;
 
 use crate::database::Database;
+use crate::utils;
 use crate::Error;
 
 pub struct AverageBlockTime {
@@ -9,11 +10,11 @@ pub struct AverageBlockTime {
 }
 
 impl AverageBlockTime {
-    pub fn new(average_block_time: f64, height: i64) -> Self {
-        Self {
-            average_block_time: Decimal::from_f64_retain(average_block_time).unwrap(),
+    pub fn new(average_block_time: f64, height: i64) -> Result<Self, Error> {
+        Ok(Self {
+            average_block_time: utils::decimal_from_f64(average_block_time)?,
             height,
-        }
+        })
     }
 
     pub async fn save_average_block_time_per_hour(&self, db: &Database) -> Result<(), Error> {
@@ -0,0 +1,203 @@
+use namada_sdk::state::Epoch;
+use namada_sdk::types::token::Amount;
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::database::Database;
+use crate::utils;
+use crate::Error;
+
+pub struct Delegation {
+    pub delegator_address: String,
+    pub validator_address: String,
+    pub amount: String,
+    pub denomination: i16,
+    pub height: i64,
+}
+
+impl Delegation {
+    pub fn new(
+        delegator_address: String,
+        validator_address: String,
+        amount: Amount,
+        height: i64,
+    ) -> Self {
+        Delegation {
+            delegator_address,
+            validator_address,
+            amount: utils::raw_amount(amount),
+            denomination: utils::NATIVE_DENOMINATION,
+            height,
+        }
+    }
+
+    // A fully unbonded delegation is removed
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO delegation (delegator_address, validator_address, amount, denomination, height)
+            VALUES ($1, $2, $3::NUMERIC, $4, $5)
+            ON CONFLICT (delegator_address, validator_address) DO UPDATE
+                SET amount = EXCLUDED.amount,
+                    height = EXCLUDED.height
+            WHERE delegation.height <= EXCLUDED.height
+            "#,
+        )
+        .bind(&self.delegator_address)
+        .bind(&self.validator_address)
+        .bind(&self.amount)
+        .bind(self.denomination)
+        .bind(self.height)
+        .execute(&db.pool())
+        .await?;
+
+        sqlx::query("DELETE FROM delegation WHERE delegator_address = $1 AND validator_address = $2 AND amount = 0")
+            .bind(&self.delegator_address)
+            .bind(&self.validator_address)
+            .execute(&db.pool())
+            .await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct Unbonding {
+    pub delegator_address: String,
+    pub validator_address: String,
+    pub amount: String,
+    pub denomination: i16,
+    pub start_epoch: i64,
+    pub withdrawable_epoch: i64,
+    pub height: i64,
+}
+
+impl Unbonding {
+    pub fn new(
+        delegator_address: String,
+        validator_address: String,
+        amount: Amount,
+        start_epoch: Epoch,
+        withdrawable_epoch: Epoch,
+        height: i64,
+    ) -> Self {
+        Unbonding {
+            delegator_address,
+            validator_address,
+            amount: utils::raw_amount(amount),
+            denomination: utils::NATIVE_DENOMINATION,
+            start_epoch: start_epoch.0 as i64,
+            withdrawable_epoch: withdrawable_epoch.0 as i64,
+            height,
+        }
+    }
+}
+
+// The unbondings of one delegator to one validator
+pub struct Unbondings {
+    pub delegator_address: String,
+    pub validator_address: String,
+    pub unbondings: Vec<Unbonding>,
+}
+
+impl Unbondings {
+    pub fn new(
+        delegator_address: String,
+        validator_address: String,
+        unbondings: Vec<Unbonding>,
+    ) -> Self {
+        Unbondings {
+            delegator_address,
+            validator_address,
+            unbondings,
+        }
+    }
+
+    // Replaces the previous unbondings, withdrawn ones are no longer returned by the node
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query("DELETE FROM unbonding WHERE delegator_address = $1 AND validator_address = $2")
+            .bind(&self.delegator_address)
+            .bind(&self.validator_address)
+            .execute(&db.pool())
+            .await?;
+
+        if self.unbondings.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO unbonding (delegator_address, validator_address, amount, denomination, start_epoch, withdrawable_epoch, height)",
+        );
+
+        builder.push_values(self.unbondings.iter(), |mut b, u| {
+            b.push_bind(u.delegator_address.clone())
+                .push_bind(u.validator_address.clone())
+                .push_bind(u.amount.clone())
+                .push_unseparated("::NUMERIC")
+                .push_bind(u.denomination)
+                .push_bind(u.start_epoch)
+                .push_bind(u.withdrawable_epoch)
+                .push_bind(u.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct Redelegation {
+    pub tx_hash: String,
+    pub delegator_address: String,
+    pub src_validator_address: String,
+    pub dest_validator_address: String,
+    pub amount: String,
+    pub denomination: i16,
+    pub height: i64,
+}
+
+impl Redelegation {
+    pub fn new(
+        tx_hash: String,
+        delegator_address: String,
+        src_validator_address: String,
+        dest_validator_address: String,
+        amount: Amount,
+        height: i64,
+    ) -> Self {
+        Redelegation {
+            tx_hash,
+            delegator_address,
+            src_validator_address,
+            dest_validator_address,
+            amount: utils::raw_amount(amount),
+            denomination: utils::NATIVE_DENOMINATION,
+            height,
+        }
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO redelegation (transaction_hash, delegator_address, src_validator_address, dest_validator_address, amount, denomination, height)
+            VALUES ($1, $2, $3, $4, $5::NUMERIC, $6, $7)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&self.tx_hash)
+        .bind(&self.delegator_address)
+        .bind(&self.src_validator_address)
+        .bind(&self.dest_validator_address)
+        .bind(&self.amount)
+        .bind(self.denomination)
+        .bind(self.height)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
@@ -212,7 +212,7 @@ impl ProposalTallyResult {
         sqlx::query(
             r#"
             INSERT INTO proposal_tally_result (proposal_id, tally_type, total, yes, abstain, no, height)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            VALUES ($1, $2, $3::NUMERIC, $4::NUMERIC, $5::NUMERIC, $6::NUMERIC, $7)
             ON CONFLICT (proposal_id) DO UPDATE SET
             total = EXCLUDED.total,
             yes = EXCLUDED.yes,
@@ -21,16 +21,21 @@ use namada_sdk::types::eth_bridge_pool;
 use namada_sdk::types::key::common::PublicKey;
 use namada_sdk::types::token;
 use sqlx::types::JsonValue;
+use sqlx::{Postgres, QueryBuilder};
 
 use crate::database::Database;
+use crate::utils;
 use crate::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Message {
     pub height: i64,
     pub tx_hash: String,
     pub message_type: String,
     pub value: JsonValue,
+    pub code_hash: Option<String>,
+    // Addresses involved in the message, with their role
+    pub addresses: Vec<(String, String)>,
 }
 
 impl Message {
@@ -40,13 +45,19 @@ impl Message {
         tx_hash: String,
         tx: NamadaTx,
     ) -> Option<Message> {
+        let code_hash = tx_code_hash(&tx);
         let message = parse_tx_to_message(checksums_map, tx);
-        if let Ok(Some((message_type, value))) = message {
+        if let Ok(Some((message_type, value, addresses))) = message {
             return Some(Message {
                 height,
                 tx_hash,
                 message_type,
                 value,
+                code_hash,
+                addresses: addresses
+                    .into_iter()
+                    .map(|(address, role)| (address, role.to_string()))
+                    .collect(),
             });
         } else if let Err(e) = message {
             tracing::error!(
@@ -63,8 +74,8 @@ impl Message {
     pub async fn save(&self, db: &Database) -> Result<(), Error> {
         sqlx::query(
             r#"
-            INSERT INTO message (height, transaction_hash, type, value)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO message (height, transaction_hash, type, value, code_hash)
+            VALUES ($1, $2, $3, $4, $5)
             ON CONFLICT DO NOTHING
             "#,
         )
@@ -72,31 +83,118 @@ impl Message {
         .bind(&self.tx_hash)
         .bind(&self.message_type)
         .bind(&self.value)
+        .bind(&self.code_hash)
         .execute(&db.pool())
         .await?;
 
+        self.save_addresses(db).await
+    }
+
+    pub async fn update(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO message (height, transaction_hash, type, value, code_hash)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (transaction_hash) DO UPDATE
+                SET type = EXCLUDED.type,
+                    value = EXCLUDED.value,
+                    code_hash = EXCLUDED.code_hash
+            "#,
+        )
+        .bind(self.height)
+        .bind(&self.tx_hash)
+        .bind(&self.message_type)
+        .bind(&self.value)
+        .bind(&self.code_hash)
+        .execute(&db.pool())
+        .await?;
+
+        sqlx::query("DELETE FROM message_address WHERE transaction_hash = $1")
+            .bind(&self.tx_hash)
+            .execute(&db.pool())
+            .await?;
+
+        self.save_addresses(db).await
+    }
+
+    async fn save_addresses(&self, db: &Database) -> Result<(), Error> {
+        if self.addresses.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("INSERT INTO message_address (transaction_hash, address, role)");
+
+        builder.push_values(self.addresses.iter(), |mut b, (address, role)| {
+            b.push_bind(self.tx_hash.clone())
+                .push_bind(address.clone())
+                .push_bind(role.clone());
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
         Ok(())
     }
+
+    // Returns the hash and height of the transactions whose message has the given type,
+    // or of every message when no type is given
+    pub async fn tx_hashes_in_range(
+        db: &Database,
+        message_type: Option<String>,
+        from_height: i64,
+        to_height: i64,
+    ) -> Result<Vec<(String, i64)>, Error> {
+        let tx_hashes = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT transaction_hash, height FROM message
+            WHERE ($1::TEXT IS NULL OR type = $1) AND height >= $2 AND height <= $3
+            ORDER BY height
+            "#,
+        )
+        .bind(message_type)
+        .bind(from_height)
+        .bind(to_height)
+        .fetch_all(&db.pool())
+        .await?;
+
+        Ok(tx_hashes)
+    }
 }
 
-fn parse_tx_to_message(
+/// Decodes the data section of a transaction according to the wasm code it
+/// runs, returning the message type, its JSON representation and the
+/// addresses it involves together with their role.
+pub fn parse_tx_to_message(
     checksums_map: &HashMap<String, String>,
     tx: NamadaTx,
-) -> Result<Option<(String, JsonValue)>, Error> {
-    let code = tx
-        .get_section(tx.code_sechash())
-        .and_then(|s| s.code_sec())
-        .map(|s| s.code.hash().0);
-
+) -> Result<Option<(String, JsonValue, Vec<(String, &'static str)>)>, Error> {
     // Return None if the code is not found
-    if code.is_none() {
-        return Ok(None);
-    }
+    let code_hex = match tx_code_hash(&tx) {
+        Some(code_hex) => code_hex,
+        None => return Ok(None),
+    };
 
-    let code_hex = String::from_utf8(hex::encode(code.unwrap().as_slice())).expect("invalid hex");
-    let unknown_type = &String::from("unknown");
+    let unknown_type = &String::from(utils::UNKNOWN_TX_TYPE);
     let tx_type = checksums_map.get(&code_hex).unwrap_or(unknown_type);
 
+    // Keep the raw data of unknown transactions, so that they can be decoded later
+    if tx_type == unknown_type {
+        let code_tag = tx
+            .get_section(tx.code_sechash())
+            .and_then(|s| s.code_sec())
+            .and_then(|s| s.tag);
+        let data = tx.data().unwrap_or_default();
+        let value = json!({
+            "code_hash": code_hex,
+            "code_tag": code_tag,
+            "data": String::from_utf8(hex::encode(&data)).expect("invalid hex"),
+            "data_length": data.len(),
+        });
+        return Ok(Some((tx_type.clone(), value, vec![])));
+    }
+
     let data = tx
         .data()
         .ok_or(Error::InvalidTxData("transaction data is empty".into()))?;
@@ -104,118 +202,148 @@ fn parse_tx_to_message(
         "tx_become_validator" => {
             let msg = pos::BecomeValidator::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.address.encode(), "validator")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_bond" => {
             let msg = pos::Bond::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![
+                (msg.validator.encode(), "validator"),
+                (msg.source.as_ref().unwrap_or(&msg.validator).encode(), "delegator"),
+            ];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_bridge_pool" => {
             let msg = eth_bridge_pool::PendingTransfer::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![
+                (msg.transfer.sender.encode(), "sender"),
+                (msg.gas_fee.payer.encode(), "fee_payer"),
+            ];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_change_consensus_key" => {
             let msg = pos::ConsensusKeyChange::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.validator.encode(), "validator")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_change_validator_commission" => {
             let msg = pos::CommissionChange::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.validator.encode(), "validator")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_change_validator_metadata" => {
             let msg = pos::MetaDataChange::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.validator.encode(), "validator")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_claim_rewards" => {
             let msg = pos::ClaimRewards::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![
+                (msg.validator.encode(), "validator"),
+                (msg.source.as_ref().unwrap_or(&msg.validator).encode(), "delegator"),
+            ];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_deactivate_validator" => {
             let msg = address::Address::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.encode(), "validator")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_ibc" => {
             // NOTE: This is a temporary solution to parse IBC messages since IBC messages are not yet supported in JSON format.
             let mut result = (tx_type.clone(), json!({}));
+            let mut addresses = vec![];
 
             if let Ok(msg) = ibc_client_msg::MsgCreateClient::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgCreateClient", value);
             } else if let Ok(msg) = ibc_client_msg::MsgUpdateClient::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgUpdateClient", value);
             } else if let Ok(msg) = ibc_client_msg::MsgSubmitMisbehaviour::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgSubmitMisbehaviour", value);
             } else if let Ok(msg) = ibc_client_msg::MsgUpgradeClient::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgUpgradeClient", value);
             } else if let Ok(msg) = ibc_connection_msg::MsgConnectionOpenInit::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgConnectionOpenInit", value);
             } else if let Ok(msg) = ibc_connection_msg::MsgConnectionOpenTry::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgConnectionOpenTry", value);
             } else if let Ok(msg) = ibc_connection_msg::MsgConnectionOpenAck::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgConnectionOpenAck", value);
             } else if let Ok(msg) = ibc_connection_msg::MsgConnectionOpenConfirm::decode(&data[..])
             {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgConnectionOpenConfirm", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgChannelOpenInit::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgChannelOpenInit", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgChannelOpenTry::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgChannelOpenTry", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgChannelOpenAck::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgChannelOpenAck", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgChannelOpenConfirm::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgChannelOpenConfirm", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgChannelCloseInit::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgChannelCloseInit", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgChannelCloseConfirm::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgChannelCloseConfirm", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgRecvPacket::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                addresses = packet_data_addresses(&msg.packet.data);
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgRecvPacket", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgAcknowledgement::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgAcknowledgement", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgTimeout::decode(&data[..]) {
-                let value = json!(msg.to_any());
-                result = (tx_type.clone(), value);
+                let value = serde_json::to_value(msg.to_any())?;
+                result = (tx_type.clone() + ".MsgTimeout", value);
             } else if let Ok(msg) = ibc_channel_msg::MsgTimeoutOnClose::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgTimeoutOnClose", value);
             } else if let Ok(msg) = ibc_transfer_msg::MsgTransfer::decode(&data[..]) {
-                let value = json!(msg.to_any());
+                addresses = vec![
+                    (msg.packet_data.sender.to_string(), "sender"),
+                    (msg.packet_data.receiver.to_string(), "receiver"),
+                ];
+                let value = serde_json::to_value(msg.to_any())?;
                 result = (tx_type.clone() + ".MsgTransfer", value);
             } else if let Ok(msg) = MsgShieldedTransfer::try_from_slice(&data[..]) {
+                let transfer = &msg.shielded_transfer.transfer;
+                addresses = vec![
+                    (msg.message.packet_data.sender.to_string(), "sender"),
+                    (msg.message.packet_data.receiver.to_string(), "receiver"),
+                    (transfer.source.encode(), "source"),
+                    (transfer.target.encode(), "target"),
+                ];
                 let value = json!({
-                    "message": msg.message.to_any(),
+                    "message": serde_json::to_value(msg.message.to_any())?,
                     "shielded_transfer": {
-                        "transfer": msg.shielded_transfer.transfer,
+                        "transfer": serde_json::to_value(msg.shielded_transfer.transfer)?,
                     },
                 });
                 result = (tx_type.clone() + ".MsgShieldedTransfer", value);
@@ -224,88 +352,118 @@ fn parse_tx_to_message(
                 //Err(Error::InvalidTxData)?;
             }
 
-            Some(result)
+            Some((result.0, result.1, addresses))
         }
         "tx_init_account" => {
             let msg = account::InitAccount::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = msg
+                .public_keys
+                .iter()
+                .map(|pk| (address::Address::from(pk).encode(), "public_key"))
+                .collect();
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_init_proposal" => {
             let msg = governance::InitProposalData::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.author.encode(), "author")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_reactivate_validator" => {
             let msg = address::Address::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.encode(), "validator")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_redelegate" => {
             let msg = pos::Redelegation::try_from_slice(&data[..])?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![
+                (msg.src_validator.encode(), "source_validator"),
+                (msg.dest_validator.encode(), "destination_validator"),
+                (msg.owner.encode(), "delegator"),
+            ];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_resign_steward" => {
             let msg = address::Address::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.encode(), "steward")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_reveal_pk" => {
             let msg = PublicKey::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(address::Address::from(&msg).encode(), "owner")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_transfer" => {
             let msg = token::Transfer::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![
+                (msg.source.encode(), "source"),
+                (msg.target.encode(), "target"),
+            ];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_unbond" => {
             let msg = pos::Unbond::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![
+                (msg.validator.encode(), "validator"),
+                (msg.source.as_ref().unwrap_or(&msg.validator).encode(), "delegator"),
+            ];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_unjail_validator" => {
             let msg = address::Address::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.encode(), "validator")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_update_account" => {
             let msg = account::UpdateAccount::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = vec![(msg.addr.encode(), "owner")];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_update_steward_commission" => {
             let msg = pgf::UpdateStewardCommission::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = std::iter::once((msg.steward.encode(), "steward"))
+                .chain(msg.commission.keys().map(|a| (a.encode(), "recipient")))
+                .collect();
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_vote_proposal" => {
             let msg = governance::VoteProposalData::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
+            let addresses = std::iter::once((msg.voter.encode(), "voter"))
+                .chain(msg.delegations.iter().map(|a| (a.encode(), "validator")))
+                .collect();
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         "tx_withdraw" => {
             let msg = pos::Withdraw::try_from_slice(&data[..])
                 .map_err(|_| Error::InvalidTxData(parse_error_message(tx_type)))?;
-            let value = json!(msg);
-            Some((tx_type.clone(), value))
-        }
-        "unknown" => {
-            let value = json!({});
-            Some((tx_type.clone(), value))
+            let addresses = vec![
+                (msg.validator.encode(), "validator"),
+                (msg.source.as_ref().unwrap_or(&msg.validator).encode(), "delegator"),
+            ];
+            let value = serde_json::to_value(msg)?;
+            Some((tx_type.clone(), value, addresses))
         }
         _ => None,
     };
@@ -313,6 +471,28 @@ fn parse_tx_to_message(
     Ok(parsed_message)
 }
 
+/// Returns the hex encoded hash of the wasm code run by the transaction.
+pub fn tx_code_hash(tx: &NamadaTx) -> Option<String> {
+    let code = tx
+        .get_section(tx.code_sechash())
+        .and_then(|s| s.code_sec())
+        .map(|s| s.code.hash().0)?;
+
+    Some(String::from_utf8(hex::encode(code.as_slice())).expect("invalid hex"))
+}
+
+// Returns the sender and receiver of an ICS-20 packet, if the packet carries a token transfer
+fn packet_data_addresses(data: &[u8]) -> Vec<(String, &'static str)> {
+    let Ok(packet_data) = serde_json::from_slice::<JsonValue>(data) else {
+        return vec![];
+    };
+
+    ["sender", "receiver"]
+        .into_iter()
+        .filter_map(|role| Some((packet_data[role].as_str()?.to_string(), role)))
+        .collect()
+}
+
 fn parse_error_message(tx_type: &str) -> String {
     format!("failed to parse to {}", tx_type)
 }
@@ -7,22 +7,28 @@ mod validator;
 pub use validator::{Validator, Validators};
 pub use validator::{
     ValidatorCommission, ValidatorCommissions, ValidatorConsensusKey, ValidatorDescription,
-    ValidatorDescriptions, ValidatorInfo, ValidatorInfos, ValidatorStatus, ValidatorStatuses,
-    ValidatorVotingPower, ValidatorVotingPowers,
+    ValidatorDescriptionChange, ValidatorDescriptions, ValidatorEvent, ValidatorHistories,
+    ValidatorInfo, ValidatorInfos, ValidatorStatus, ValidatorStatuses, ValidatorVotingPower,
+    ValidatorVotingPowers,
 };
 
 mod block;
-pub use block::Block;
+pub use block::{Block, ValidatorProposedBlocks};
 
 mod pre_commmit;
 pub use pre_commmit::PreCommit;
 pub use pre_commmit::PreCommits;
+pub use pre_commmit::ValidatorUptimes;
+pub use pre_commmit::{FLAG_ABSENT, FLAG_COMMIT, FLAG_NIL};
 
 mod message;
-pub use message::Message;
+pub use message::{parse_tx_to_message, Message};
 
 mod tx;
-pub use tx::Tx;
+pub use tx::{Tx, TxFee, TxSigners};
+
+mod tx_code;
+pub use tx_code::TxCodes;
 
 mod consensus;
 pub use consensus::AverageBlockTime;
@@ -30,6 +36,25 @@ pub use consensus::AverageBlockTime;
 mod gov;
 pub use gov::{Proposal, ProposalVote, ProposalTallyResult};
 
+mod bank;
+pub use bank::{AccountBalance, AccountBalances, TokenTransfer};
+
+mod supply;
+pub use supply::{Supplies, Supply};
+
+mod account;
+pub use account::{Account, RevealedPk};
+
+mod delegation;
+pub use delegation::{Delegation, Redelegation, Unbonding, Unbondings};
+
+mod reward;
+pub use reward::{RewardClaim, ValidatorReward, ValidatorRewards};
+
+mod slashing;
+pub use slashing::{
+    Evidences, ValidatorJailing, ValidatorJailings, ValidatorSlash, ValidatorSlashes,
+};
 
 #[derive(Clone)]
 pub struct Database {
@@ -7,28 +7,36 @@ use crate::database::Database;
 use crate::utils;
 use crate::Error;
 
+// Flags of the commit signatures
+pub const FLAG_COMMIT: &str = "commit";
+pub const FLAG_NIL: &str = "nil";
+pub const FLAG_ABSENT: &str = "absent";
+
 pub struct PreCommit {
-    pub validator_address: String,
+    pub consensus_address: String,
     pub height: i64,
     pub timestamp: DateTime<Utc>,
     pub voting_power: i64,
     pub proposer_priority: i64,
+    pub flag: String,
 }
 
 impl PreCommit {
     pub fn new(
-        validator_address: String,
+        consensus_address: String,
         height: i64,
         timestamp: DateTime<Utc>,
         voting_power: i64,
         proposer_priority: i64,
+        flag: &str,
     ) -> Self {
         PreCommit {
-            validator_address,
+            consensus_address,
             height,
             timestamp,
             voting_power,
             proposer_priority,
+            flag: flag.to_string(),
         }
     }
 
@@ -37,42 +45,43 @@ impl PreCommit {
         validator_address: tendermint::account::Id,
         validators: Vec<TmValidatorInfo>,
         timestamp: tendermint::Time,
-    ) -> Self {
+        flag: &str,
+    ) -> Result<Self, Error> {
         match utils::find_validator(validators.clone(), validator_address) {
             Some(info) => {
-                return PreCommit::new(
-                    utils::addr_to_bech32(validator_address),
+                return Ok(PreCommit::new(
+                    utils::consensus_address(validator_address),
                     height as i64,
-                    DateTime::from_timestamp(timestamp.unix_timestamp(), 0)
-                        .expect("invalid timestamp"),
+                    utils::timestamp(timestamp)?,
                     info.power.into(),
                     info.proposer_priority.into(),
-                )
+                    flag,
+                ))
             }
             //
             None => {
                 tracing::error!(
                     "validator not found {} at height {}",
-                    utils::addr_to_bech32(validator_address),
+                    utils::consensus_address(validator_address),
                     height,
                 );
-                return PreCommit::new(
-                    utils::addr_to_bech32(validator_address),
+                return Ok(PreCommit::new(
+                    utils::consensus_address(validator_address),
                     height as i64,
-                    DateTime::from_timestamp(timestamp.unix_timestamp(), 0)
-                        .expect("invalid timestamp"),
+                    utils::timestamp(timestamp)?,
                     0,
                     0,
-                );
+                    flag,
+                ));
             }
         };
 
         // TODO: Consider this code after investigating the issue that find_validator returns None
         // let validator = utils::find_validator(validators.clone(), validator_address)
-        //     .expect(format!("validator not found {}", utils::addr_to_bech32(validator_address)).as_str());
+        //     .expect(format!("validator not found {}", utils::consensus_address(validator_address)).as_str());
 
         // PreCommit::new(
-        //     utils::addr_to_bech32(validator_address),
+        //     utils::consensus_address(validator_address),
         //     height as i64,
         //     DateTime::from_timestamp(timestamp.unix_timestamp(), 0).expect("invalid timestamp"),
         //     validator.power.into(),
@@ -86,16 +95,21 @@ pub struct PreCommits(Vec<PreCommit>);
 impl PreCommits {
     pub async fn save(&self, db: &Database) -> Result<(), Error> {
         let mut builder: QueryBuilder<Postgres> =
-        QueryBuilder::new("INSERT INTO pre_commit (validator_address, height, timestamp, voting_power, proposer_priority)");
+        QueryBuilder::new("INSERT INTO pre_commit (consensus_address, height, timestamp, voting_power, proposer_priority, flag)");
 
         builder.push_values(self.0.iter(), |mut b, p| {
-            b.push_bind(p.validator_address.clone())
+            b.push_bind(p.consensus_address.clone())
                 .push_bind(p.height)
                 .push_bind(p.timestamp)
                 .push_bind(p.voting_power)
-                .push_bind(p.proposer_priority);
+                .push_bind(p.proposer_priority)
+                .push_bind(p.flag.clone());
         });
-        builder.push("ON CONFLICT DO NOTHING");
+        builder.push(
+            "ON CONFLICT (height, consensus_address) DO UPDATE \
+            SET flag = EXCLUDED.flag, \
+                timestamp = EXCLUDED.timestamp",
+        );
 
         let query = builder.build();
         query.execute(&db.pool()).await?;
@@ -109,3 +123,101 @@ impl From<Vec<PreCommit>> for PreCommits {
         PreCommits(pre_commits)
     }
 }
+
+//--------------------------------------------------------
+
+pub struct ValidatorUptimes;
+
+impl ValidatorUptimes {
+    // Shifts the windows ending at the previous height to the height, adding the pre-commits
+    // of the height and subtracting the ones leaving the window. Windows not ending at the
+    // previous height, e.g. of blocks processed out of order, wait for the next recompute.
+    pub async fn update(db: &Database, window: u64, height: u64) -> Result<(), Error> {
+        if window == 0 {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            WITH entering AS (
+                SELECT EXISTS (SELECT 1 FROM pre_commit WHERE height = $2) AS indexed
+            ), leaving AS (
+                SELECT EXISTS (SELECT 1 FROM pre_commit WHERE height = $2 - $1) AS indexed
+            )
+            UPDATE validator_uptime uptime
+            SET indexed = uptime.indexed + entering.indexed::INT - leaving.indexed::INT,
+                signed = uptime.signed
+                    + (SELECT COUNT(*) FROM pre_commit
+                       WHERE height = $2 AND consensus_address = uptime.consensus_address
+                         AND flag = 'commit')
+                    - (SELECT COUNT(*) FROM pre_commit
+                       WHERE height = $2 - $1 AND consensus_address = uptime.consensus_address
+                         AND flag = 'commit'),
+                missed = uptime.missed
+                    + (SELECT COUNT(*) FROM pre_commit
+                       WHERE height = $2 AND consensus_address = uptime.consensus_address
+                         AND flag <> 'commit')
+                    - (SELECT COUNT(*) FROM pre_commit
+                       WHERE height = $2 - $1 AND consensus_address = uptime.consensus_address
+                         AND flag <> 'commit'),
+                height = $2
+            FROM entering, leaving
+            WHERE uptime.height = $2 - 1 AND uptime.window_size = $1
+            "#,
+        )
+        .bind(window as i64)
+        .bind(height as i64)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+
+    // Counts the signed and missed blocks of the validators over the `window` heights
+    // ending at `height`, from the saved pre-commits. Heights of the window not indexed
+    // yet, e.g. during catch-up, are neither signed nor missed.
+    pub async fn recompute(
+        db: &Database,
+        consensus_addresses: Vec<String>,
+        window: u64,
+        height: u64,
+    ) -> Result<(), Error> {
+        if consensus_addresses.is_empty() || window == 0 {
+            return Ok(());
+        }
+
+        sqlx::query(
+            r#"
+            WITH indexed AS (
+                SELECT COUNT(DISTINCT height) AS count
+                FROM pre_commit
+                WHERE height > $3 - $2 AND height <= $3
+            )
+            INSERT INTO validator_uptime (consensus_address, window_size, indexed, signed, missed, height)
+            SELECT consensus_address,
+                   $2,
+                   indexed.count,
+                   COUNT(*) FILTER (WHERE flag = 'commit'),
+                   COUNT(*) FILTER (WHERE flag <> 'commit'),
+                   $3
+            FROM pre_commit, indexed
+            WHERE consensus_address = ANY($1) AND height > $3 - $2 AND height <= $3
+            GROUP BY consensus_address, indexed.count
+            ON CONFLICT (consensus_address) DO UPDATE
+                SET window_size = EXCLUDED.window_size,
+                    indexed = EXCLUDED.indexed,
+                    signed = EXCLUDED.signed,
+                    missed = EXCLUDED.missed,
+                    height = EXCLUDED.height
+            WHERE validator_uptime.height <= EXCLUDED.height
+            "#,
+        )
+        .bind(consensus_addresses)
+        .bind(window as i64)
+        .bind(height as i64)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use namada_sdk::types::token::Amount;
+use sqlx::types::Decimal;
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::database::Database;
+use crate::utils;
+use crate::Error;
+
+pub struct RewardClaim {
+    pub tx_hash: String,
+    pub delegator_address: String,
+    pub validator_address: String,
+    pub amount: String,
+    pub denomination: i16,
+    pub height: i64,
+}
+
+impl RewardClaim {
+    pub fn new(
+        tx_hash: String,
+        delegator_address: String,
+        validator_address: String,
+        amount: Amount,
+        height: i64,
+    ) -> Self {
+        RewardClaim {
+            tx_hash,
+            delegator_address,
+            validator_address,
+            amount: utils::raw_amount(amount),
+            denomination: utils::NATIVE_DENOMINATION,
+            height,
+        }
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO reward_claim (transaction_hash, delegator_address, validator_address, amount, denomination, height)
+            VALUES ($1, $2, $3, $4::NUMERIC, $5, $6)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&self.tx_hash)
+        .bind(&self.delegator_address)
+        .bind(&self.validator_address)
+        .bind(&self.amount)
+        .bind(self.denomination)
+        .bind(self.height)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct ValidatorReward {
+    pub validator_address: String,
+    pub rewards_product: Decimal,
+    pub denomination: i16,
+    pub epoch: i64,
+    pub height: i64,
+}
+
+impl ValidatorReward {
+    pub fn new(
+        validator_address: String,
+        rewards_product: Decimal,
+        epoch: u64,
+        height: u64,
+    ) -> Self {
+        ValidatorReward {
+            validator_address,
+            rewards_product,
+            denomination: utils::NATIVE_DENOMINATION,
+            epoch: epoch as i64,
+            height: height as i64,
+        }
+    }
+}
+
+pub struct ValidatorRewards(Vec<ValidatorReward>);
+
+impl From<Vec<ValidatorReward>> for ValidatorRewards {
+    fn from(rewards: Vec<ValidatorReward>) -> Self {
+        ValidatorRewards(rewards)
+    }
+}
+
+impl ValidatorRewards {
+    // The bonds earned the validator's stake over the epoch times the rewards product; epochs without an indexed stake are skipped.
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            r#"
+            INSERT INTO validator_reward (validator_address, rewards_product, stake, amount, denomination, epoch, height)
+            SELECT rewards.validator_address,
+                   rewards.rewards_product,
+                   history.voting_power,
+                   TRUNC(history.voting_power * rewards.rewards_product),
+                   rewards.denomination,
+                   rewards.epoch,
+                   rewards.height
+            FROM ("#,
+        );
+
+        builder.push_values(self.0.iter(), |mut b, r| {
+            b.push_bind(r.validator_address.clone())
+                .push_bind(r.rewards_product)
+                .push_bind(r.denomination)
+                .push_bind(r.epoch)
+                .push_bind(r.height);
+        });
+        builder.push(
+            r#"
+            ) AS rewards (validator_address, rewards_product, denomination, epoch, height)
+            JOIN validator_voting_power_history history
+                ON history.validator_address = rewards.validator_address
+                AND history.epoch = rewards.epoch
+            ON CONFLICT DO NOTHING
+            "#,
+        );
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
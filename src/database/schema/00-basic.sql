@@ -1,9 +1,17 @@
+/* Token amounts are stored as NUMERIC in the smallest unit of their token, format_amount
+   gives their decimal value from the denomination of the token */
+CREATE FUNCTION format_amount(amount NUMERIC, denomination SMALLINT) RETURNS NUMERIC AS
+$$
+SELECT amount / POWER(10::NUMERIC, denomination)
+$$ LANGUAGE SQL IMMUTABLE;
+
 CREATE TABLE validator
 (
-    consensus_address TEXT NOT NULL PRIMARY KEY, /* Validator consensus address */
+    consensus_address TEXT NOT NULL PRIMARY KEY, /* Hex encoded CometBFT address of the consensus key */
     consensus_pubkey  TEXT NOT NULL UNIQUE /* Validator consensus public key */,
-    validator_address TEXT
+    validator_address TEXT /* Namada address of the validator owning the consensus key */
 );
+CREATE INDEX validator_validator_address_index ON validator (validator_address);
 
 CREATE TABLE block
 (
@@ -12,23 +20,47 @@ CREATE TABLE block
     num_txs          INTEGER DEFAULT 0,
     total_gas        BIGINT  DEFAULT 0,
     proposer_address TEXT,
-    timestamp        TIMESTAMP WITHOUT TIME ZONE NOT NULL
+    timestamp        TIMESTAMP WITHOUT TIME ZONE NOT NULL,
+    epoch            BIGINT                      NOT NULL
 );
 CREATE INDEX block_height_index ON block (height);
 CREATE INDEX block_hash_index ON block (hash);
 CREATE INDEX block_proposer_address_index ON block (proposer_address);
+CREATE INDEX block_epoch_index ON block (epoch);
+
+CREATE TABLE validator_proposed_blocks
+(
+    consensus_address TEXT    NOT NULL,
+    validator_address TEXT, /* Resolved through the validator table */
+    epoch             BIGINT  NOT NULL,
+    count             BIGINT  NOT NULL,
+    expected_count    NUMERIC NOT NULL, /* Proposals expected from the voting power of the validator */
+    PRIMARY KEY (consensus_address, epoch)
+);
+CREATE INDEX validator_proposed_blocks_validator_address_index ON validator_proposed_blocks (validator_address);
+CREATE INDEX validator_proposed_blocks_epoch_index ON validator_proposed_blocks (epoch);
 
 CREATE TABLE pre_commit
 (
-    validator_address TEXT                        NOT NULL,
+    consensus_address TEXT                        NOT NULL, /* See validator.consensus_address */
     height            BIGINT                      NOT NULL,
     timestamp         TIMESTAMP WITHOUT TIME ZONE NOT NULL,
     voting_power      BIGINT                      NOT NULL,
     proposer_priority BIGINT                      NOT NULL,
-    UNIQUE (validator_address, timestamp)
+    flag              TEXT                        NOT NULL, /* commit, nil or absent */
+    PRIMARY KEY (height, consensus_address)
+);
+CREATE INDEX pre_commit_consensus_address_index ON pre_commit (consensus_address);
+
+CREATE TABLE validator_uptime
+(
+    consensus_address TEXT   NOT NULL PRIMARY KEY,
+    window_size       BIGINT NOT NULL, /* Liveness window of the PoS parameters, in blocks */
+    indexed           BIGINT NOT NULL, /* Heights of the window indexed so far, the others are unknown */
+    signed            BIGINT NOT NULL,
+    missed            BIGINT NOT NULL, /* Nil and absent signatures */
+    height            BIGINT NOT NULL
 );
-CREATE INDEX pre_commit_validator_address_index ON pre_commit (validator_address);
-CREATE INDEX pre_commit_height_index ON pre_commit (height);
 
 CREATE TABLE transaction
 (
@@ -45,27 +77,64 @@ CREATE TABLE transaction
     gas_used     BIGINT           DEFAULT 0,
     raw_log      TEXT,
 
+    /* Hash of the inner tx, shared by a wrapper and its decrypted tx */
+    raw_hash     TEXT,
+
+    /* Wrapper tx fee */
+    fee_amount_per_gas_unit NUMERIC,
+    fee_denomination        SMALLINT,
+    fee_token               TEXT,
+    gas_limit               BIGINT,
+    fee_payer               TEXT,
+    fee_payer_pubkey        TEXT,
+
     CONSTRAINT unique_tx UNIQUE (hash)
 );
 CREATE INDEX transaction_hash_index ON transaction (hash);
 CREATE INDEX transaction_height_index ON transaction (height);
+CREATE INDEX transaction_raw_hash_index ON transaction (raw_hash);
+CREATE INDEX transaction_fee_payer_index ON transaction (fee_payer);
+
+CREATE TABLE transaction_signer
+(
+    transaction_hash TEXT     NOT NULL,
+    section_hash     TEXT     NOT NULL, /* Hash of the signature section */
+    signer_index     SMALLINT NOT NULL,
+    public_key       TEXT, /* Not available for multisig accounts */
+    address          TEXT     NOT NULL,
+    CONSTRAINT unique_transaction_signer UNIQUE (transaction_hash, section_hash, signer_index)
+);
+CREATE INDEX transaction_signer_transaction_hash_index ON transaction_signer (transaction_hash);
+CREATE INDEX transaction_signer_public_key_index ON transaction_signer (public_key);
+CREATE INDEX transaction_signer_address_index ON transaction_signer (address);
+
+CREATE TABLE tx_code
+(
+    code_hash TEXT NOT NULL PRIMARY KEY, /* Hash of the tx wasm code */
+    tx_type   TEXT NOT NULL
+);
+CREATE INDEX tx_code_tx_type_index ON tx_code (tx_type);
 
 CREATE TABLE message
 (
     transaction_hash            TEXT   NOT NULL,
     type                        TEXT   NOT NULL,
     value                       JSONB  NOT NULL,
+    code_hash                   TEXT,
 
     height                      BIGINT NOT NULL,
     CONSTRAINT unique_message_per_tx UNIQUE (transaction_hash)
 );
 CREATE INDEX message_transaction_hash_index ON message (transaction_hash);
 CREATE INDEX message_type_index ON message (type);
+CREATE INDEX message_code_hash_index ON message (code_hash);
 
-CREATE TABLE validator_voting_power
+CREATE TABLE message_address
 (
-    height            BIGINT NOT NULL,
-    validator_address TEXT   NOT NULL,
-    voting_power      BIGINT NOT NULL,
-    UNIQUE (height, validator_address)
-);
\ No newline at end of file
+    transaction_hash TEXT NOT NULL,
+    address          TEXT NOT NULL,
+    role             TEXT NOT NULL, /* e.g. source, target, validator, delegator, voter */
+    CONSTRAINT unique_message_address UNIQUE (transaction_hash, address, role)
+);
+CREATE INDEX message_address_transaction_hash_index ON message_address (transaction_hash);
+CREATE INDEX message_address_address_index ON message_address (address);
@@ -0,0 +1,20 @@
+CREATE TABLE account
+(
+    address            TEXT   NOT NULL PRIMARY KEY,
+    type               TEXT   NOT NULL, /* implicit, established or internal */
+    public_keys        TEXT[] NOT NULL DEFAULT '{}',
+    threshold          SMALLINT,
+    vp_code_hash       TEXT, /* Not set for implicit accounts */
+    creation_height    BIGINT NOT NULL, /* First height the account is known at */
+    last_update_height BIGINT NOT NULL
+);
+CREATE INDEX account_type_index ON account (type);
+
+CREATE TABLE revealed_pk
+(
+    public_key       TEXT   NOT NULL PRIMARY KEY,
+    address          TEXT   NOT NULL,
+    transaction_hash TEXT   NOT NULL,
+    height           BIGINT NOT NULL
+);
+CREATE INDEX revealed_pk_address_index ON revealed_pk (address);
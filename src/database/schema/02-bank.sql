@@ -0,0 +1,55 @@
+CREATE TABLE token_transfer
+(
+    transaction_hash TEXT     NOT NULL,
+    height           BIGINT   NOT NULL,
+    source           TEXT     NOT NULL,
+    target           TEXT     NOT NULL,
+    token            TEXT     NOT NULL, /* Token address, or denom of IBC transfers */
+    amount           NUMERIC  NOT NULL, /* Raw amount, in the smallest unit of the token */
+    denomination     SMALLINT NOT NULL,
+    key              TEXT,
+    shielded         BOOLEAN  NOT NULL,
+    CONSTRAINT unique_token_transfer UNIQUE (transaction_hash)
+);
+CREATE INDEX token_transfer_height_index ON token_transfer (height);
+CREATE INDEX token_transfer_source_index ON token_transfer (source);
+CREATE INDEX token_transfer_target_index ON token_transfer (target);
+CREATE INDEX token_transfer_token_index ON token_transfer (token);
+
+CREATE TABLE account_balance
+(
+    address      TEXT     NOT NULL,
+    token        TEXT     NOT NULL,
+    amount       NUMERIC  NOT NULL, /* Raw amount, in the smallest unit of the token */
+    denomination SMALLINT NOT NULL, /* Decimal places of the token, 0 for IBC tokens */
+    height       BIGINT   NOT NULL,
+    PRIMARY KEY (address, token)
+);
+CREATE INDEX account_balance_address_index ON account_balance (address);
+CREATE INDEX account_balance_token_index ON account_balance (token);
+
+CREATE TABLE account_balance_history
+(
+    address      TEXT     NOT NULL,
+    token        TEXT     NOT NULL,
+    amount       NUMERIC  NOT NULL,
+    denomination SMALLINT NOT NULL,
+    height       BIGINT   NOT NULL,
+    CONSTRAINT unique_account_balance_history UNIQUE (address, token, height)
+);
+CREATE INDEX account_balance_history_address_index ON account_balance_history (address);
+CREATE INDEX account_balance_history_height_index ON account_balance_history (height);
+
+CREATE TABLE supply
+(
+    token         TEXT     NOT NULL,
+    total_supply  NUMERIC  NOT NULL, /* Raw amount, in the smallest unit of the token */
+    denomination  SMALLINT NOT NULL, /* Decimal places of the token, 0 for IBC tokens */
+    bonded        NUMERIC, /* Only set for the native token */
+    staking_ratio NUMERIC, /* bonded / total_supply */
+    epoch         BIGINT   NOT NULL,
+    height        BIGINT   NOT NULL,
+    CONSTRAINT unique_supply UNIQUE (token, epoch)
+);
+CREATE INDEX supply_token_index ON supply (token);
+CREATE INDEX supply_epoch_index ON supply (epoch);
@@ -3,16 +3,16 @@
 
 CREATE TABLE validator_info
 (
-    consensus_address   TEXT   NOT NULL PRIMARY KEY,
-    max_change_rate     TEXT   NOT NULL,
-    height              BIGINT NOT NULL
+    consensus_address   TEXT    NOT NULL PRIMARY KEY,
+    max_change_rate     NUMERIC NOT NULL,
+    height              BIGINT  NOT NULL
 );
 
 CREATE TABLE validator_voting_power
 (
-    validator_address TEXT   NOT NULL PRIMARY KEY,
-    voting_power      BIGINT NOT NULL,
-    height            BIGINT NOT NULL
+    validator_address TEXT    NOT NULL PRIMARY KEY,
+    voting_power      NUMERIC NOT NULL, /* Stake, in the smallest unit of the native token */
+    height            BIGINT  NOT NULL
 );
 CREATE INDEX validator_voting_power_height_index ON validator_voting_power (height);
 
@@ -37,9 +37,123 @@ CREATE INDEX validator_status_height_index ON validator_status (height);
 CREATE TABLE validator_description
 (
     validator_address TEXT   NOT NULL PRIMARY KEY,
+    name              TEXT, /* Not exposed by the validator metadata of the SDK yet */
     avatar_url        TEXT,
     website           TEXT,
     details           TEXT,
+    email             TEXT,
+    discord_handle    TEXT,
     height            BIGINT NOT NULL
 );
-CREATE INDEX validator_description_height_index ON validator_description (height);
\ No newline at end of file
+CREATE INDEX validator_description_height_index ON validator_description (height);
+
+CREATE TABLE validator_event
+(
+    transaction_hash  TEXT   NOT NULL PRIMARY KEY,
+    validator_address TEXT   NOT NULL,
+    type              TEXT   NOT NULL, /* Message type without the tx_ prefix, e.g. unjail_validator */
+    value             JSONB  NOT NULL,
+    height            BIGINT NOT NULL
+);
+CREATE INDEX validator_event_validator_address_index ON validator_event (validator_address);
+CREATE INDEX validator_event_height_index ON validator_event (height);
+
+/* ---- VALIDATORS HISTORY ---- */
+
+CREATE TABLE validator_consensus_key_history
+(
+    validator_address TEXT   NOT NULL,
+    consensus_pubkey  TEXT   NOT NULL,
+    consensus_address TEXT   NOT NULL, /* See validator.consensus_address */
+    from_epoch        BIGINT NOT NULL,
+    to_epoch          BIGINT, /* NULL while the key is in use */
+    CONSTRAINT unique_validator_consensus_key_history UNIQUE (validator_address, consensus_pubkey, from_epoch)
+);
+CREATE INDEX validator_consensus_key_history_validator_address_index ON validator_consensus_key_history (validator_address);
+CREATE INDEX validator_consensus_key_history_consensus_address_index ON validator_consensus_key_history (consensus_address);
+
+CREATE TABLE validator_voting_power_history
+(
+    validator_address TEXT    NOT NULL,
+    voting_power      NUMERIC NOT NULL,
+    epoch             BIGINT  NOT NULL,
+    height            BIGINT  NOT NULL,
+    CONSTRAINT unique_validator_voting_power_history UNIQUE (validator_address, epoch)
+);
+CREATE INDEX validator_voting_power_history_epoch_index ON validator_voting_power_history (epoch);
+
+CREATE TABLE validator_commission_history
+(
+    validator_address TEXT    NOT NULL,
+    commission        DECIMAL NOT NULL,
+    epoch             BIGINT  NOT NULL,
+    height            BIGINT  NOT NULL,
+    CONSTRAINT unique_validator_commission_history UNIQUE (validator_address, epoch)
+);
+CREATE INDEX validator_commission_history_epoch_index ON validator_commission_history (epoch);
+
+CREATE TABLE validator_status_history
+(
+    validator_address TEXT    NOT NULL,
+    status            INT     NOT NULL,
+    jailed            BOOLEAN NOT NULL,
+    epoch             BIGINT  NOT NULL,
+    height            BIGINT  NOT NULL,
+    CONSTRAINT unique_validator_status_history UNIQUE (validator_address, epoch)
+);
+CREATE INDEX validator_status_history_epoch_index ON validator_status_history (epoch);
+
+CREATE TABLE validator_description_history
+(
+    validator_address TEXT   NOT NULL,
+    name              TEXT, /* Not exposed by the validator metadata of the SDK yet */
+    avatar_url        TEXT,
+    website           TEXT,
+    details           TEXT,
+    email             TEXT,
+    discord_handle    TEXT,
+    epoch             BIGINT NOT NULL,
+    height            BIGINT NOT NULL,
+    CONSTRAINT unique_validator_description_history UNIQUE (validator_address, epoch)
+);
+CREATE INDEX validator_description_history_epoch_index ON validator_description_history (epoch);
+
+/* ---- DELEGATIONS ---- */
+
+CREATE TABLE delegation
+(
+    delegator_address TEXT     NOT NULL,
+    validator_address TEXT     NOT NULL,
+    amount            NUMERIC  NOT NULL, /* Bonded amount net of slashes, in the smallest unit */
+    denomination      SMALLINT NOT NULL, /* Decimal places of the native token */
+    height            BIGINT   NOT NULL,
+    PRIMARY KEY (delegator_address, validator_address)
+);
+CREATE INDEX delegation_validator_address_index ON delegation (validator_address);
+
+CREATE TABLE unbonding
+(
+    delegator_address  TEXT     NOT NULL,
+    validator_address  TEXT     NOT NULL,
+    amount             NUMERIC  NOT NULL,
+    denomination       SMALLINT NOT NULL,
+    start_epoch        BIGINT   NOT NULL,
+    withdrawable_epoch BIGINT   NOT NULL,
+    height             BIGINT   NOT NULL,
+    CONSTRAINT unique_unbonding UNIQUE (delegator_address, validator_address, start_epoch, withdrawable_epoch)
+);
+CREATE INDEX unbonding_delegator_address_index ON unbonding (delegator_address);
+CREATE INDEX unbonding_validator_address_index ON unbonding (validator_address);
+
+CREATE TABLE redelegation
+(
+    transaction_hash       TEXT     NOT NULL PRIMARY KEY,
+    delegator_address      TEXT     NOT NULL,
+    src_validator_address  TEXT     NOT NULL,
+    dest_validator_address TEXT     NOT NULL,
+    amount                 NUMERIC  NOT NULL,
+    denomination           SMALLINT NOT NULL,
+    height                 BIGINT   NOT NULL
+);
+CREATE INDEX redelegation_delegator_address_index ON redelegation (delegator_address);
+CREATE INDEX redelegation_height_index ON redelegation (height);
@@ -0,0 +1,26 @@
+CREATE TABLE reward_claim
+(
+    transaction_hash  TEXT     NOT NULL PRIMARY KEY,
+    delegator_address TEXT     NOT NULL,
+    validator_address TEXT     NOT NULL,
+    amount            NUMERIC  NOT NULL, /* Claimed amount, in the smallest unit of the native token */
+    denomination      SMALLINT NOT NULL, /* Decimal places of the native token */
+    height            BIGINT   NOT NULL
+);
+CREATE INDEX reward_claim_delegator_address_index ON reward_claim (delegator_address);
+CREATE INDEX reward_claim_validator_address_index ON reward_claim (validator_address);
+CREATE INDEX reward_claim_height_index ON reward_claim (height);
+
+CREATE TABLE validator_reward
+(
+    validator_address TEXT     NOT NULL,
+    rewards_product   NUMERIC  NOT NULL, /* Reward rate of the bonds over the epoch, commission excluded */
+    stake             NUMERIC  NOT NULL, /* Stake of the validator over the epoch */
+    amount            NUMERIC  NOT NULL, /* Earned by the bonds to the validator, stake * rewards product */
+    denomination      SMALLINT NOT NULL, /* Decimal places of the native token, for stake and amount */
+    epoch             BIGINT   NOT NULL,
+    height            BIGINT   NOT NULL,
+    CONSTRAINT unique_validator_reward UNIQUE (validator_address, epoch)
+);
+CREATE INDEX validator_reward_validator_address_index ON validator_reward (validator_address);
+CREATE INDEX validator_reward_epoch_index ON validator_reward (epoch);
@@ -30,10 +30,10 @@ CREATE TABLE proposal_tally_result
 (
     proposal_id  INTEGER    REFERENCES proposal (id) PRIMARY KEY,
     tally_type   TEXT       NOT NULL,
-    total        TEXT       NOT NULL,
-    yes          TEXT       NOT NULL,
-    abstain      TEXT       NOT NULL,
-    no           TEXT       NOT NULL,
+    total        NUMERIC    NOT NULL, /* Voting powers, in the smallest unit of the native token */
+    yes          NUMERIC    NOT NULL,
+    abstain      NUMERIC    NOT NULL,
+    no           NUMERIC    NOT NULL,
     height       BIGINT     NOT NULL,
     CONSTRAINT unique_tally_result UNIQUE (proposal_id)
 );
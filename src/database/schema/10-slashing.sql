@@ -0,0 +1,39 @@
+CREATE TABLE evidence
+(
+    height             BIGINT    NOT NULL,
+    type               TEXT      NOT NULL, /* duplicate_vote or light_client_attack */
+    consensus_address  TEXT      NOT NULL,
+    evidence_height    BIGINT    NOT NULL, /* Height of the infraction */
+    total_voting_power BIGINT    NOT NULL,
+    validator_power    BIGINT,
+    timestamp          TIMESTAMP NOT NULL,
+    value              JSONB     NOT NULL,
+    CONSTRAINT unique_evidence UNIQUE (type, consensus_address, evidence_height)
+);
+CREATE INDEX evidence_height_index ON evidence (height);
+CREATE INDEX evidence_consensus_address_index ON evidence (consensus_address);
+CREATE INDEX evidence_evidence_height_index ON evidence (evidence_height);
+
+CREATE TABLE validator_slash
+(
+    validator_address TEXT     NOT NULL,
+    type              TEXT     NOT NULL,
+    rate              NUMERIC  NOT NULL,
+    amount            NUMERIC  NOT NULL, /* Stake at the infraction epoch times the rate */
+    denomination      SMALLINT NOT NULL, /* Decimal places of the native token */
+    epoch             BIGINT   NOT NULL, /* Epoch of the infraction */
+    infraction_height BIGINT   NOT NULL,
+    CONSTRAINT unique_validator_slash UNIQUE (validator_address, epoch, infraction_height, type)
+);
+CREATE INDEX validator_slash_validator_address_index ON validator_slash (validator_address);
+CREATE INDEX validator_slash_infraction_height_index ON validator_slash (infraction_height);
+
+CREATE TABLE validator_jailing
+(
+    validator_address TEXT   NOT NULL,
+    infraction_height BIGINT, /* Matches evidence.evidence_height, NULL when jailed for inactivity */
+    epoch             BIGINT NOT NULL,
+    height            BIGINT NOT NULL,
+    CONSTRAINT unique_validator_jailing UNIQUE (validator_address, epoch)
+);
+CREATE INDEX validator_jailing_validator_address_index ON validator_jailing (validator_address);
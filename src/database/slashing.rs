@@ -0,0 +1,259 @@
+use chrono::NaiveDateTime;
+use namada_sdk::proof_of_stake::types::Slash;
+use namada_sdk::types::token::Amount;
+use sqlx::types::JsonValue;
+use sqlx::{Postgres, QueryBuilder};
+use std::collections::HashSet;
+use tendermint::block::Block as TmBlock;
+use tendermint::evidence::Evidence as TmEvidence;
+
+use crate::database::Database;
+use crate::utils;
+use crate::Error;
+
+pub struct Evidence {
+    pub height: i64,
+    pub evidence_type: String,
+    pub consensus_address: String,
+    pub evidence_height: i64,
+    pub total_voting_power: i64,
+    pub validator_power: Option<i64>,
+    pub timestamp: NaiveDateTime,
+    pub value: JsonValue,
+}
+
+pub struct Evidences(Vec<Evidence>);
+
+impl Evidences {
+    // Light client attacks are stored once per byzantine validator
+    pub fn from_tm_block(block: &TmBlock) -> Result<Self, Error> {
+        let height: i64 = block.header.height.into();
+        let mut evidences = vec![];
+
+        for evidence in block.evidence.iter() {
+            let value = serde_json::to_value(evidence)?;
+            match evidence {
+                TmEvidence::DuplicateVote(e) => evidences.push(Evidence {
+                    height,
+                    evidence_type: "duplicate_vote".to_string(),
+                    consensus_address: utils::consensus_address(e.vote_a.validator_address),
+                    evidence_height: e.vote_a.height.into(),
+                    total_voting_power: e.total_voting_power.into(),
+                    validator_power: Some(e.validator_power.into()),
+                    timestamp: utils::timestamp(e.timestamp)?.naive_utc(),
+                    value,
+                }),
+                TmEvidence::LightClientAttack(e) => {
+                    for validator in e.byzantine_validators.iter() {
+                        evidences.push(Evidence {
+                            height,
+                            evidence_type: "light_client_attack".to_string(),
+                            consensus_address: utils::consensus_address(validator.address),
+                            evidence_height: e.common_height.into(),
+                            total_voting_power: e.total_voting_power.into(),
+                            validator_power: Some(validator.power.into()),
+                            timestamp: utils::timestamp(e.timestamp)?.naive_utc(),
+                            value: value.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(Evidences(evidences))
+    }
+
+    // Returns the latest infraction of the validator among the evidences included during the
+    // given epoch or the previous one, under any of its consensus keys
+    pub async fn infraction_height(
+        db: &Database,
+        validator_address: &str,
+        epoch: u64,
+    ) -> Result<Option<u64>, Error> {
+        let (height,): (Option<i64>,) = sqlx::query_as(
+            r#"
+            SELECT MAX(evidence.evidence_height)
+            FROM evidence
+            JOIN block ON block.height = evidence.height
+            WHERE block.epoch BETWEEN $2 - 1 AND $2
+              AND evidence.consensus_address IN (
+                  SELECT consensus_address FROM validator WHERE validator_address = $1
+                  UNION
+                  SELECT consensus_address FROM validator_consensus_key_history WHERE validator_address = $1
+              )
+            "#,
+        )
+        .bind(validator_address)
+        .bind(epoch as i64)
+        .fetch_one(&db.pool())
+        .await?;
+
+        Ok(height.map(|h| h as u64))
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO evidence (height, type, consensus_address, evidence_height, total_voting_power, validator_power, timestamp, value)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, e| {
+            b.push_bind(e.height)
+                .push_bind(e.evidence_type.clone())
+                .push_bind(e.consensus_address.clone())
+                .push_bind(e.evidence_height)
+                .push_bind(e.total_voting_power)
+                .push_bind(e.validator_power)
+                .push_bind(e.timestamp)
+                .push_bind(e.value.clone());
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct ValidatorSlash {
+    pub validator_address: String,
+    pub slash_type: String,
+    pub rate: String,
+    // Stake of the validator at the infraction epoch, the slashed amount is stake * rate
+    // truncated to the smallest unit
+    pub stake: String,
+    pub denomination: i16,
+    pub epoch: i64,
+    pub infraction_height: i64,
+}
+
+impl ValidatorSlash {
+    pub fn new(validator_address: String, slash: &Slash, stake: Amount) -> Self {
+        ValidatorSlash {
+            validator_address,
+            slash_type: slash.r#type.to_string(),
+            rate: slash.rate.to_string(),
+            stake: utils::raw_amount(stake),
+            denomination: utils::NATIVE_DENOMINATION,
+            epoch: slash.epoch.0 as i64,
+            infraction_height: slash.block_height as i64,
+        }
+    }
+}
+
+pub struct ValidatorSlashes(Vec<ValidatorSlash>);
+
+impl From<Vec<ValidatorSlash>> for ValidatorSlashes {
+    fn from(slashes: Vec<ValidatorSlash>) -> Self {
+        ValidatorSlashes(slashes)
+    }
+}
+
+impl ValidatorSlashes {
+    // Returns the (validator address, epoch, infraction height) of the saved slashes
+    pub async fn saved(db: &Database) -> Result<HashSet<(String, i64, i64)>, Error> {
+        let slashes = sqlx::query_as::<_, (String, i64, i64)>(
+            "SELECT validator_address, epoch, infraction_height FROM validator_slash",
+        )
+        .fetch_all(&db.pool())
+        .await?;
+
+        Ok(slashes.into_iter().collect())
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO validator_slash (validator_address, type, rate, amount, denomination, epoch, infraction_height)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, s| {
+            b.push_bind(s.validator_address.clone())
+                .push_bind(s.slash_type.clone())
+                .push_bind(s.rate.clone())
+                .push_unseparated("::NUMERIC")
+                .push("TRUNC(")
+                .push_bind_unseparated(s.stake.clone())
+                .push_unseparated("::NUMERIC * ")
+                .push_bind_unseparated(s.rate.clone())
+                .push_unseparated("::NUMERIC)")
+                .push_bind(s.denomination)
+                .push_bind(s.epoch)
+                .push_bind(s.infraction_height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct ValidatorJailing {
+    pub validator_address: String,
+    // Infraction height of the slash that caused the jailing, if any
+    pub infraction_height: Option<i64>,
+    pub epoch: i64,
+    pub height: i64,
+}
+
+impl ValidatorJailing {
+    pub fn new(
+        validator_address: String,
+        infraction_height: Option<u64>,
+        epoch: u64,
+        height: u64,
+    ) -> Self {
+        ValidatorJailing {
+            validator_address,
+            infraction_height: infraction_height.map(|h| h as i64),
+            epoch: epoch as i64,
+            height: height as i64,
+        }
+    }
+}
+
+pub struct ValidatorJailings(Vec<ValidatorJailing>);
+
+impl From<Vec<ValidatorJailing>> for ValidatorJailings {
+    fn from(jailings: Vec<ValidatorJailing>) -> Self {
+        ValidatorJailings(jailings)
+    }
+}
+
+impl ValidatorJailings {
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO validator_jailing (validator_address, infraction_height, epoch, height)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, j| {
+            b.push_bind(j.validator_address.clone())
+                .push_bind(j.infraction_height)
+                .push_bind(j.epoch)
+                .push_bind(j.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
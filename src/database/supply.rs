@@ -0,0 +1,78 @@
+use namada_sdk::types::token::Amount;
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::database::Database;
+use crate::utils;
+use crate::Error;
+
+pub struct Supply {
+    pub token: String,
+    pub total_supply: String,
+    pub denomination: i16,
+    // Only known for the native token
+    pub bonded: Option<String>,
+    pub epoch: i64,
+    pub height: i64,
+}
+
+impl Supply {
+    pub fn new(
+        token: String,
+        total_supply: Amount,
+        denomination: u8,
+        bonded: Option<Amount>,
+        epoch: u64,
+        height: u64,
+    ) -> Self {
+        Supply {
+            token,
+            total_supply: utils::raw_amount(total_supply),
+            denomination: denomination as i16,
+            bonded: bonded.map(utils::raw_amount),
+            epoch: epoch as i64,
+            height: height as i64,
+        }
+    }
+}
+
+pub struct Supplies(Vec<Supply>);
+
+impl From<Vec<Supply>> for Supplies {
+    fn from(supplies: Vec<Supply>) -> Self {
+        Supplies(supplies)
+    }
+}
+
+impl Supplies {
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO supply (token, total_supply, denomination, bonded, staking_ratio, epoch, height)",
+        );
+
+        // The staking ratio is computed by the database to keep the precision of the amounts
+        builder.push_values(self.0.iter(), |mut b, s| {
+            b.push_bind(s.token.clone())
+                .push_bind(s.total_supply.clone())
+                .push_unseparated("::NUMERIC")
+                .push_bind(s.denomination)
+                .push_bind(s.bonded.clone())
+                .push_unseparated("::NUMERIC")
+                .push_bind(s.bonded.clone())
+                .push_unseparated("::NUMERIC / NULLIF(")
+                .push_bind_unseparated(s.total_supply.clone())
+                .push_unseparated("::NUMERIC, 0)")
+                .push_bind(s.epoch)
+                .push_bind(s.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
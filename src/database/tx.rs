@@ -1,4 +1,10 @@
+use namada_sdk::tx::data::WrapperTx;
+use namada_sdk::tx::{Section, Signer, Tx as NamadaTx};
+use namada_sdk::types::address::Address;
+use sqlx::{Postgres, QueryBuilder};
+
 use crate::database::Database;
+use crate::utils;
 use crate::Error;
 
 #[derive(Debug)]
@@ -13,6 +19,9 @@ pub struct Tx {
     pub gas_wanted: i64,
     pub gas_used: i64,
     pub raw_log: String,
+
+    pub raw_hash: String,
+    pub fee: Option<TxFee>,
 }
 
 impl Tx {
@@ -25,6 +34,8 @@ impl Tx {
         gas_wanted: i64,
         gas_used: i64,
         raw_log: String,
+        raw_hash: String,
+        fee: Option<TxFee>,
     ) -> Self {
         Self {
             hash,
@@ -35,14 +46,18 @@ impl Tx {
             gas_wanted,
             gas_used,
             raw_log,
+            raw_hash,
+            fee,
         }
     }
 
     pub async fn save(&self, db: &Database) -> Result<(), Error> {
         sqlx::query(
             r#"
-            INSERT INTO transaction (hash, height, success, memo, tx_type, gas_wanted, gas_used, raw_log)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT DO NOTHING"#,
+            INSERT INTO transaction (hash, height, success, memo, tx_type, gas_wanted, gas_used, raw_log,
+                raw_hash, fee_amount_per_gas_unit, fee_denomination, fee_token, gas_limit, fee_payer,
+                fee_payer_pubkey)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10::NUMERIC, $11, $12, $13, $14, $15) ON CONFLICT DO NOTHING"#,
         )
         .bind(self.hash.clone())
         .bind(self.height)
@@ -52,9 +67,115 @@ impl Tx {
         .bind(self.gas_wanted)
         .bind(self.gas_used)
         .bind(self.raw_log.clone())
+        .bind(self.raw_hash.clone())
+        .bind(self.fee.as_ref().map(|f| f.amount_per_gas_unit.clone()))
+        .bind(self.fee.as_ref().map(|f| f.denomination))
+        .bind(self.fee.as_ref().map(|f| f.token.clone()))
+        .bind(self.fee.as_ref().map(|f| f.gas_limit))
+        .bind(self.fee.as_ref().map(|f| f.payer.clone()))
+        .bind(self.fee.as_ref().map(|f| f.payer_pubkey.clone()))
         .execute(&db.pool())
         .await?;
 
         Ok(())
     }
 }
+
+//--------------------------------------------------------
+
+#[derive(Debug)]
+pub struct TxFee {
+    pub amount_per_gas_unit: String,
+    pub denomination: i16,
+    pub token: String,
+    pub gas_limit: i64,
+    pub payer: String,
+    pub payer_pubkey: String,
+}
+
+impl TxFee {
+    pub fn from_wrapper(wrapper: &WrapperTx) -> Self {
+        Self {
+            amount_per_gas_unit: utils::raw_amount(wrapper.fee.amount_per_gas_unit.amount()),
+            denomination: u8::from(wrapper.fee.amount_per_gas_unit.denom()) as i16,
+            token: wrapper.fee.token.encode(),
+            gas_limit: u64::from(wrapper.gas_limit.clone()) as i64,
+            payer: wrapper.fee_payer().encode(),
+            payer_pubkey: wrapper.pk.to_string(),
+        }
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct TxSigner {
+    pub tx_hash: String,
+    pub section_hash: String,
+    pub signer_index: i16,
+    pub public_key: Option<String>,
+    pub address: String,
+}
+
+pub struct TxSigners(Vec<TxSigner>);
+
+impl TxSigners {
+    pub fn from_tx(tx_hash: String, tx: &NamadaTx) -> Self {
+        let mut signers = vec![];
+
+        for section in tx.sections.iter() {
+            let signature = match section {
+                Section::Signature(signature) => signature,
+                _ => continue,
+            };
+            let section_hash = section.get_hash().to_string();
+
+            for index in signature.signatures.keys() {
+                let (public_key, address) = match &signature.signer {
+                    Signer::PubKeys(public_keys) => match public_keys.get(*index as usize) {
+                        Some(public_key) => (
+                            Some(public_key.to_string()),
+                            Address::from(public_key).encode(),
+                        ),
+                        None => continue,
+                    },
+                    // Multisig accounts only reference the index of their public keys
+                    Signer::Address(address) => (None, address.encode()),
+                };
+
+                signers.push(TxSigner {
+                    tx_hash: tx_hash.clone(),
+                    section_hash: section_hash.clone(),
+                    signer_index: *index as i16,
+                    public_key,
+                    address,
+                });
+            }
+        }
+
+        TxSigners(signers)
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO transaction_signer (transaction_hash, section_hash, signer_index, public_key, address)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, s| {
+            b.push_bind(s.tx_hash.clone())
+                .push_bind(s.section_hash.clone())
+                .push_bind(s.signer_index)
+                .push_bind(s.public_key.clone())
+                .push_bind(s.address.clone());
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use sqlx::{FromRow, Postgres, QueryBuilder};
+use std::collections::HashMap;
+
+use crate::database::Database;
+use crate::utils;
+use crate::Error;
+
+#[derive(FromRow)]
+pub struct TxCode {
+    pub code_hash: String,
+    pub tx_type: String,
+}
+
+impl TxCode {
+    pub fn new(code_hash: String, tx_type: String) -> Self {
+        TxCode { code_hash, tx_type }
+    }
+}
+
+pub struct TxCodes(Vec<TxCode>);
+
+impl From<HashMap<String, String>> for TxCodes {
+    fn from(checksums_map: HashMap<String, String>) -> Self {
+        TxCodes(
+            checksums_map
+                .into_iter()
+                .map(|(code_hash, tx_type)| TxCode::new(code_hash, tx_type))
+                .collect(),
+        )
+    }
+}
+
+impl TxCodes {
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        let mut builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("INSERT INTO tx_code (code_hash, tx_type)");
+
+        builder.push_values(self.0.iter(), |mut b, c| {
+            b.push_bind(c.code_hash.clone())
+                .push_bind(c.tx_type.clone());
+        });
+        // A name found later replaces unknown, known names are kept
+        builder.push(
+            r#"
+            ON CONFLICT (code_hash) DO UPDATE
+                SET tx_type = EXCLUDED.tx_type
+            WHERE tx_code.tx_type = "#,
+        );
+        builder.push_bind(utils::UNKNOWN_TX_TYPE);
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+
+    // Returns every known code hash, including the ones of previous Namada
+    // versions that are no longer listed in the current checksums file.
+    pub async fn load(db: &Database) -> Result<HashMap<String, String>, Error> {
+        let tx_codes = sqlx::query_as::<_, TxCode>(r#"SELECT code_hash, tx_type FROM tx_code"#)
+            .fetch_all(&db.pool())
+            .await?;
+
+        Ok(tx_codes
+            .into_iter()
+            .map(|c| (c.code_hash, c.tx_type))
+            .collect())
+    }
+}
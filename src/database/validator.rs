@@ -1,10 +1,13 @@
-use sqlx::types::Decimal;
+use sqlx::types::{Decimal, JsonValue};
 use sqlx::{Postgres, QueryBuilder};
-use std::str::FromStr;
+use std::collections::HashSet;
 
 use namada_sdk::proof_of_stake::types::ValidatorState;
+use namada_sdk::types::token::Amount;
+use tendermint::PublicKey as TmPublicKey;
 
 use crate::database::Database;
+use crate::utils;
 use crate::Error;
 
 pub struct Validator {
@@ -55,12 +58,12 @@ impl From<Vec<Validator>> for Validators {
 
 pub struct ValidatorInfo {
     pub consensus_address: String,
-    pub max_change_rate: String,
+    pub max_change_rate: Decimal,
     pub height: i64,
 }
 
 impl ValidatorInfo {
-    pub fn new(consensus_address: String, max_change_rate: String, height: u64) -> Self {
+    pub fn new(consensus_address: String, max_change_rate: Decimal, height: u64) -> Self {
         ValidatorInfo {
             consensus_address,
             max_change_rate,
@@ -108,17 +111,20 @@ impl ValidatorInfos {
 
 //--------------------------------------------------------
 
+// The voting power is the stake of the validator, in the smallest unit of the native token
 pub struct ValidatorVotingPower {
     pub validator_address: String,
-    pub voting_power: i64,
+    pub voting_power: String,
+    pub epoch: i64,
     pub height: i64,
 }
 
 impl ValidatorVotingPower {
-    pub fn new(validator_address: String, voting_power: i64, height: u64) -> Self {
+    pub fn new(validator_address: String, voting_power: Amount, epoch: u64, height: u64) -> Self {
         ValidatorVotingPower {
             validator_address,
-            voting_power,
+            voting_power: utils::raw_amount(voting_power),
+            epoch: epoch as i64,
             height: height as i64,
         }
     }
@@ -144,7 +150,8 @@ impl ValidatorVotingPowers {
 
         builder.push_values(self.0.iter(), |mut b, v| {
             b.push_bind(v.validator_address.clone())
-                .push_bind(v.voting_power)
+                .push_bind(v.voting_power.clone())
+                .push_unseparated("::NUMERIC")
                 .push_bind(v.height);
         });
         builder.push(
@@ -158,6 +165,23 @@ impl ValidatorVotingPowers {
         let query = builder.build();
         query.execute(&db.pool()).await?;
 
+        // History
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO validator_voting_power_history (validator_address, voting_power, epoch, height)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, v| {
+            b.push_bind(v.validator_address.clone())
+                .push_bind(v.voting_power.clone())
+                .push_unseparated("::NUMERIC")
+                .push_bind(v.epoch)
+                .push_bind(v.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
         Ok(())
     }
 }
@@ -167,14 +191,16 @@ impl ValidatorVotingPowers {
 pub struct ValidatorCommission {
     pub validator_address: String,
     pub commission_rate: Decimal,
+    pub epoch: i64,
     pub height: i64,
 }
 
 impl ValidatorCommission {
-    pub fn new(address: String, commission_rate: String, height: u64) -> Self {
+    pub fn new(address: String, commission_rate: Decimal, epoch: u64, height: u64) -> Self {
         ValidatorCommission {
             validator_address: address,
-            commission_rate: Decimal::from_str(&commission_rate).unwrap(),
+            commission_rate,
+            epoch: epoch as i64,
             height: height as i64,
         }
     }
@@ -213,6 +239,22 @@ impl ValidatorCommissions {
         let query = builder.build();
         query.execute(&db.pool()).await?;
 
+        // History
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO validator_commission_history (validator_address, commission, epoch, height)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, v| {
+            b.push_bind(v.validator_address.clone())
+                .push_bind(v.commission_rate.clone())
+                .push_bind(v.epoch)
+                .push_bind(v.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
         Ok(())
     }
 }
@@ -223,15 +265,17 @@ pub struct ValidatorStatus {
     pub validator_address: String,
     pub status: i64,
     pub jailed: bool,
+    pub epoch: i64,
     pub height: i64,
 }
 
 impl ValidatorStatus {
-    pub fn new(validator_address: String, state: ValidatorState, height: u64) -> Self {
+    pub fn new(validator_address: String, state: ValidatorState, epoch: u64, height: u64) -> Self {
         ValidatorStatus {
             validator_address,
             status: state as i64,
             jailed: ValidatorState::Jailed == state,
+            epoch: epoch as i64,
             height: height as i64,
         }
     }
@@ -246,6 +290,25 @@ impl From<Vec<Option<ValidatorStatus>>> for ValidatorStatuses {
 }
 
 impl ValidatorStatuses {
+    pub async fn validators(db: &Database) -> Result<Vec<String>, Error> {
+        let validators =
+            sqlx::query_as::<_, (String,)>("SELECT validator_address FROM validator_status")
+                .fetch_all(&db.pool())
+                .await?;
+
+        Ok(validators.into_iter().map(|(address,)| address).collect())
+    }
+
+    pub async fn jailed_validators(db: &Database) -> Result<HashSet<String>, Error> {
+        let validators = sqlx::query_as::<_, (String,)>(
+            "SELECT validator_address FROM validator_status WHERE jailed",
+        )
+        .fetch_all(&db.pool())
+        .await?;
+
+        Ok(validators.into_iter().map(|(address,)| address).collect())
+    }
+
     pub async fn save(&self, db: &Database) -> Result<(), Error> {
         if self.0.is_empty() {
             return Ok(());
@@ -272,6 +335,23 @@ impl ValidatorStatuses {
         let query = builder.build();
         query.execute(&db.pool()).await?;
 
+        // History
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO validator_status_history (validator_address, status, jailed, epoch, height)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, v| {
+            b.push_bind(v.validator_address.clone())
+                .push_bind(v.status)
+                .push_bind(v.jailed)
+                .push_bind(v.epoch)
+                .push_bind(v.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
         Ok(())
     }
 }
@@ -280,25 +360,37 @@ impl ValidatorStatuses {
 
 pub struct ValidatorDescription {
     pub validator_address: String,
+    pub name: Option<String>,
     pub avatar_url: String,
     pub website: String,
     pub details: String,
+    pub email: String,
+    pub discord_handle: String,
+    pub epoch: i64,
     pub height: i64,
 }
 
 impl ValidatorDescription {
     pub fn new(
         validator_address: String,
+        name: Option<String>,
         avatar_url: String,
         website: String,
         details: String,
+        email: String,
+        discord_handle: String,
+        epoch: u64,
         height: u64,
     ) -> Self {
         ValidatorDescription {
             validator_address,
+            name,
             avatar_url,
             website,
             details,
+            email,
+            discord_handle,
+            epoch: epoch as i64,
             height: height as i64,
         }
     }
@@ -319,21 +411,27 @@ impl ValidatorDescriptions {
         }
 
         let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
-            "INSERT INTO validator_description (validator_address, avatar_url, website, details, height)",
+            "INSERT INTO validator_description (validator_address, name, avatar_url, website, details, email, discord_handle, height)",
         );
 
         builder.push_values(self.0.iter(), |mut b, v| {
             b.push_bind(v.validator_address.clone())
+                .push_bind(v.name.clone())
                 .push_bind(v.avatar_url.clone())
                 .push_bind(v.website.clone())
                 .push_bind(v.details.clone())
+                .push_bind(v.email.clone())
+                .push_bind(v.discord_handle.clone())
                 .push_bind(v.height);
         });
         builder.push(
             "ON CONFLICT (validator_address) DO UPDATE \
-            SET avatar_url = EXCLUDED.avatar_url, \
+            SET name = EXCLUDED.name, \
+                avatar_url = EXCLUDED.avatar_url, \
                 website = EXCLUDED.website, \
                 details = EXCLUDED.details, \
+                email = EXCLUDED.email, \
+                discord_handle = EXCLUDED.discord_handle, \
                 height = EXCLUDED.height \
         WHERE validator_description.height <= EXCLUDED.height",
         );
@@ -341,6 +439,184 @@ impl ValidatorDescriptions {
         let query = builder.build();
         query.execute(&db.pool()).await?;
 
+        // History
+        let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO validator_description_history (validator_address, name, avatar_url, website, details, email, discord_handle, epoch, height)",
+        );
+
+        builder.push_values(self.0.iter(), |mut b, v| {
+            b.push_bind(v.validator_address.clone())
+                .push_bind(v.name.clone())
+                .push_bind(v.avatar_url.clone())
+                .push_bind(v.website.clone())
+                .push_bind(v.details.clone())
+                .push_bind(v.email.clone())
+                .push_bind(v.discord_handle.clone())
+                .push_bind(v.epoch)
+                .push_bind(v.height);
+        });
+        builder.push("ON CONFLICT DO NOTHING");
+
+        let query = builder.build();
+        query.execute(&db.pool()).await?;
+
+        Ok(())
+    }
+}
+
+// Fields set by a validator metadata change, unset ones are kept
+pub struct ValidatorDescriptionChange {
+    pub validator_address: String,
+    pub name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website: Option<String>,
+    pub details: Option<String>,
+    pub email: Option<String>,
+    pub discord_handle: Option<String>,
+    pub height: i64,
+}
+
+impl ValidatorDescriptionChange {
+    pub fn new(
+        validator_address: String,
+        name: Option<String>,
+        avatar_url: Option<String>,
+        website: Option<String>,
+        details: Option<String>,
+        email: Option<String>,
+        discord_handle: Option<String>,
+        height: i64,
+    ) -> Self {
+        ValidatorDescriptionChange {
+            validator_address,
+            name,
+            avatar_url,
+            website,
+            details,
+            email,
+            discord_handle,
+            height,
+        }
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO validator_description (validator_address, name, avatar_url, website, details, email, discord_handle, height)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (validator_address) DO UPDATE
+                SET name = COALESCE(EXCLUDED.name, validator_description.name),
+                    avatar_url = COALESCE(EXCLUDED.avatar_url, validator_description.avatar_url),
+                    website = COALESCE(EXCLUDED.website, validator_description.website),
+                    details = COALESCE(EXCLUDED.details, validator_description.details),
+                    email = COALESCE(EXCLUDED.email, validator_description.email),
+                    discord_handle = COALESCE(EXCLUDED.discord_handle, validator_description.discord_handle),
+                    height = EXCLUDED.height
+            WHERE validator_description.height <= EXCLUDED.height
+            "#,
+        )
+        .bind(&self.validator_address)
+        .bind(&self.name)
+        .bind(&self.avatar_url)
+        .bind(&self.website)
+        .bind(&self.details)
+        .bind(&self.email)
+        .bind(&self.discord_handle)
+        .bind(self.height)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct ValidatorHistories;
+
+impl ValidatorHistories {
+    // Copies the current voting power, commission, status and description of the validators
+    // without history at the epoch, i.e. the ones not refreshed since their last change
+    pub async fn carry_forward(db: &Database, epoch: u64, height: u64) -> Result<(), Error> {
+        let queries = [
+            r#"
+            INSERT INTO validator_voting_power_history (validator_address, voting_power, epoch, height)
+            SELECT validator_address, voting_power, $1, $2 FROM validator_voting_power
+            ON CONFLICT DO NOTHING
+            "#,
+            r#"
+            INSERT INTO validator_commission_history (validator_address, commission, epoch, height)
+            SELECT validator_address, commission, $1, $2 FROM validator_commission
+            ON CONFLICT DO NOTHING
+            "#,
+            r#"
+            INSERT INTO validator_status_history (validator_address, status, jailed, epoch, height)
+            SELECT validator_address, status, jailed, $1, $2 FROM validator_status
+            ON CONFLICT DO NOTHING
+            "#,
+            r#"
+            INSERT INTO validator_description_history (validator_address, name, avatar_url, website, details, email, discord_handle, epoch, height)
+            SELECT validator_address, name, avatar_url, website, details, email, discord_handle, $1, $2
+            FROM validator_description
+            ON CONFLICT DO NOTHING
+            "#,
+        ];
+
+        for query in queries {
+            sqlx::query(query)
+                .bind(epoch as i64)
+                .bind(height as i64)
+                .execute(&db.pool())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+//--------------------------------------------------------
+
+pub struct ValidatorEvent {
+    pub tx_hash: String,
+    pub validator_address: String,
+    pub event_type: String,
+    pub value: JsonValue,
+    pub height: i64,
+}
+
+impl ValidatorEvent {
+    pub fn new(
+        tx_hash: String,
+        validator_address: String,
+        event_type: String,
+        value: JsonValue,
+        height: i64,
+    ) -> Self {
+        ValidatorEvent {
+            tx_hash,
+            validator_address,
+            event_type,
+            value,
+            height,
+        }
+    }
+
+    pub async fn save(&self, db: &Database) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO validator_event (transaction_hash, validator_address, type, value, height)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&self.tx_hash)
+        .bind(&self.validator_address)
+        .bind(&self.event_type)
+        .bind(&self.value)
+        .bind(self.height)
+        .execute(&db.pool())
+        .await?;
+
         Ok(())
     }
 }
@@ -348,25 +624,74 @@ impl ValidatorDescriptions {
 //--------------------------------------------------------
 
 pub struct ValidatorConsensusKey {
+    pub consensus_address: String,
     pub consensus_pubkey: String,
     pub validator_address: String,
 }
 
 impl ValidatorConsensusKey {
-    pub fn new(consensus_pubkey: String, validator_address: String) -> Self {
+    pub fn new(consensus_pubkey: TmPublicKey, validator_address: String) -> Self {
         ValidatorConsensusKey {
-            consensus_pubkey,
+            consensus_pubkey: consensus_pubkey.to_bech32(utils::COMMON_PK_HRP),
+            consensus_address: utils::consensus_address_from_pubkey(consensus_pubkey),
             validator_address,
         }
     }
 
+    // The key may not have signed any block yet, e.g. right after a consensus key change
     pub async fn save(&self, db: &Database) -> Result<(), Error> {
-        let query = "UPDATE validator SET validator_address = $2 WHERE consensus_pubkey = $1";
-        sqlx::query(query)
-            .bind(&self.consensus_pubkey)
-            .bind(&self.validator_address)
-            .execute(&db.pool())
-            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO validator (consensus_address, consensus_pubkey, validator_address)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (consensus_address) DO UPDATE
+                SET validator_address = EXCLUDED.validator_address
+            "#,
+        )
+        .bind(&self.consensus_address)
+        .bind(&self.consensus_pubkey)
+        .bind(&self.validator_address)
+        .execute(&db.pool())
+        .await?;
+
+        Ok(())
+    }
+
+    // Records the key in use by the validator at the given epoch, closing the range of
+    // the previous key on rotations
+    pub async fn save_history(&self, db: &Database, epoch: u64) -> Result<(), Error> {
+        sqlx::query(
+            r#"
+            UPDATE validator_consensus_key_history
+            SET to_epoch = $3 - 1
+            WHERE validator_address = $1
+              AND to_epoch IS NULL
+              AND consensus_pubkey <> $2
+              AND from_epoch < $3
+            "#,
+        )
+        .bind(&self.validator_address)
+        .bind(&self.consensus_pubkey)
+        .bind(epoch as i64)
+        .execute(&db.pool())
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO validator_consensus_key_history (validator_address, consensus_pubkey, consensus_address, from_epoch)
+            SELECT $1, $2, $3, $4
+            WHERE NOT EXISTS (
+                SELECT 1 FROM validator_consensus_key_history
+                WHERE validator_address = $1 AND to_epoch IS NULL
+            )
+            "#,
+        )
+        .bind(&self.validator_address)
+        .bind(&self.consensus_pubkey)
+        .bind(&self.consensus_address)
+        .bind(epoch as i64)
+        .execute(&db.pool())
+        .await?;
 
         Ok(())
     }
@@ -11,6 +11,8 @@ use tokio::task::JoinError;
 pub enum Error {
     #[error("Invalid Transaction data, reason: {0}")]
     InvalidTxData(String),
+    #[error("Invalid chain data: {0}")]
+    InvalidChainData(String),
 
     #[error("Tendermint error: {0}")]
     TendermintError(#[from] TError),
@@ -24,6 +26,8 @@ pub enum Error {
 
     #[error("Configuration error: {0}")]
     IO(#[from] std::io::Error),
+    #[error("Invalid arguments: {0}")]
+    InvalidArgs(String),
 
     #[error("Address parsing error: {0}")]
     AddrError(#[from] std::net::AddrParseError),
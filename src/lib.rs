@@ -0,0 +1,9 @@
+pub mod config;
+pub mod database;
+pub mod error;
+pub mod modules;
+pub mod node;
+pub mod utils;
+pub mod worker;
+
+pub use error::Error;
@@ -1,5 +1,4 @@
-use modules::ModuleBasic;
-use modules::StakingModule;
+use std::collections::HashMap;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -16,16 +15,11 @@ use futures_util::pin_mut;
 use futures_util::Stream;
 use tokio::task::{JoinHandle, JoinSet};
 
-use error::Error;
 use tendermint_rpc::HttpClient;
 
-mod config;
-mod database;
-mod error;
-mod modules;
-mod node;
-mod utils;
-mod worker;
+use namadajuno::modules::ModuleBasic;
+use namadajuno::modules::StakingModule;
+use namadajuno::{config, database, modules, node, utils, worker, Error};
 
 // Max number of queued blocks in channel.
 // this can be adjusted for optimal performance, however
@@ -49,10 +43,76 @@ async fn main() -> Result<(), Error> {
     let client = HttpClient::new(config.node.config.rpc.address.as_ref()).unwrap();
     let node = node::Node::new(client.clone());
 
-    start(config, node).await?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(|arg| arg.as_str()) {
+        Some("reparse") => reparse(config, node, &args[1..]).await?,
+        _ => start(config, node).await?,
+    }
     Ok(())
 }
 
+const REPARSE_USAGE: &str =
+    "reparse messages [--type <message type>] [--from <height>] [--to <height>]";
+
+async fn reparse(config: config::Config, node: node::Node, args: &[String]) -> Result<(), Error> {
+    if args.first().map(|arg| arg.as_str()) != Some("messages") {
+        return Err(Error::InvalidArgs(REPARSE_USAGE.into()));
+    }
+
+    let mut message_type = None;
+    let mut from_height = config.parsing.start_height;
+    let mut to_height = None;
+    let mut flags = args[1..].iter();
+    while let Some(flag) = flags.next() {
+        let value = flags
+            .next()
+            .ok_or(Error::InvalidArgs(REPARSE_USAGE.into()))?;
+        match flag.as_str() {
+            "--type" => message_type = Some(value.clone()),
+            "--from" => from_height = value.parse()?,
+            "--to" => to_height = Some(value.parse()?),
+            _ => return Err(Error::InvalidArgs(REPARSE_USAGE.into())),
+        }
+    }
+    let to_height = match to_height {
+        Some(height) => height,
+        None => node.latest_height().await?,
+    };
+
+    let db: database::Database = database::Database::new(&config.database).await?;
+    let checksums = load_tx_codes(&node, &db).await?;
+
+    // Blocks are not enqueued, the channel is only required by the context
+    let (tx, rx): (Sender<u64>, Receiver<u64>) = async_channel::bounded(1);
+    let ctx = worker::Context::new(
+        tx,
+        rx,
+        node.clone(),
+        db.clone(),
+        checksums,
+        StakingModule::new(node.clone(), db.clone()),
+        modules::GovModule::new(node.clone(), db.clone()),
+        modules::BankModule::new(node.clone(), db.clone()),
+        modules::SupplyModule::new(node.clone(), db.clone()),
+        modules::AccountModule::new(db.clone()),
+    );
+
+    worker::reparse_messages(&ctx, message_type, from_height, to_height).await
+}
+
+async fn load_tx_codes(
+    node: &node::Node,
+    db: &database::Database,
+) -> Result<HashMap<String, String>, Error> {
+    // Discover the tx codes from the chain, the checksums file takes precedence
+    let mut checksums = node.tx_codes().await?;
+    checksums.extend(utils::load_checksums()?);
+
+    // Merge the known tx codes with the ones of previous Namada versions
+    database::TxCodes::from(checksums).save(db).await?;
+    database::TxCodes::load(db).await
+}
+
 async fn start(config: config::Config, node: node::Node) -> Result<(), Error> {
     let db: database::Database = database::Database::new(&config.database).await?;
 
@@ -82,9 +142,13 @@ async fn start(config: config::Config, node: node::Node) -> Result<(), Error> {
     let staking = StakingModule::new(node.clone(), db.clone());
     let consensus = modules::ConsensusModule::new(db.clone());
     let gov = modules::GovModule::new(node.clone(), db.clone());
+    let bank = modules::BankModule::new(node.clone(), db.clone());
+    let supply = modules::SupplyModule::new(node.clone(), db.clone());
+    let account = modules::AccountModule::new(db.clone());
 
     // Setup and start scheduler
     let mut scheduler = Scheduler::new();
+    staking.register_periodic_operations(&mut scheduler);
     consensus.register_periodic_operations(&mut scheduler);
     gov.register_periodic_operations(&mut scheduler);
     tokio::spawn(async move {
@@ -94,15 +158,20 @@ async fn start(config: config::Config, node: node::Node) -> Result<(), Error> {
         }
     });
 
+    let checksums = load_tx_codes(&node, &db).await?;
+
     // Setup worker context
     let ctx = Arc::new(worker::Context::new(
         tx.clone(),
         rx,
         node.clone(),
         db.clone(),
-        utils::load_checksums()?,
+        checksums,
         staking,
         gov,
+        bank,
+        supply,
+        account,
     ));
 
     // Start workers
@@ -0,0 +1,90 @@
+use clokwerk::Scheduler;
+use namada_sdk::account::{InitAccount, UpdateAccount};
+use namada_sdk::state::Epoch;
+use namada_sdk::types::address::Address;
+use namada_sdk::types::key::common::PublicKey;
+
+use crate::database::{Account, Database, Message, RevealedPk};
+use crate::modules::ModuleBasic;
+use crate::Error;
+
+#[derive(Clone)]
+pub struct AccountModule {
+    db: Database,
+}
+
+impl AccountModule {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+impl ModuleBasic for AccountModule {
+    fn register_periodic_operations(&self, _scheduler: &mut Scheduler) {}
+
+    async fn handle_epoch(&self, _height: u64, _epoch: Epoch) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn handle_message(&self, message: Message) -> Result<(), Error> {
+        match message.message_type.as_str() {
+            "tx_init_account" => {
+                let msg = serde_json::from_value::<InitAccount>(message.value)?;
+                let public_keys = msg
+                    .public_keys
+                    .iter()
+                    .map(|pk| pk.to_string())
+                    .collect::<Vec<_>>();
+
+                // The address is only known from the tx result
+                for (address, role) in message.addresses.iter() {
+                    if role != "initialized_account" {
+                        continue;
+                    }
+
+                    Account::new(
+                        &Address::decode(address)?,
+                        public_keys.clone(),
+                        Some(msg.threshold),
+                        Some(hex::encode(msg.vp_code_hash.0)),
+                        message.height,
+                    )
+                    .save(&self.db)
+                    .await?;
+                }
+            }
+            "tx_update_account" => {
+                let msg = serde_json::from_value::<UpdateAccount>(message.value)?;
+                Account::new(
+                    &msg.addr,
+                    msg.public_keys.iter().map(|pk| pk.to_string()).collect(),
+                    msg.threshold,
+                    msg.vp_code_hash.map(|hash| hex::encode(hash.0)),
+                    message.height,
+                )
+                .save(&self.db)
+                .await?;
+            }
+            "tx_reveal_pk" => {
+                let pk = serde_json::from_value::<PublicKey>(message.value)?;
+                let address = Address::from(&pk);
+                RevealedPk::new(
+                    pk.to_string(),
+                    address.encode(),
+                    message.tx_hash,
+                    message.height,
+                )
+                .save(&self.db)
+                .await?;
+
+                // Implicit accounts are only known once their public key is revealed
+                Account::new(&address, vec![pk.to_string()], Some(1), None, message.height)
+                    .save(&self.db)
+                    .await?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use clokwerk::Scheduler;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use namada_sdk::ibc::apps::transfer::types::msgs::transfer::MsgTransfer;
+use namada_sdk::ibc::core::channel::types::msgs::MsgRecvPacket;
+use namada_sdk::ibc::primitives::proto::Any;
+use namada_sdk::ibc::storage::ibc_token;
+use namada_sdk::state::Epoch;
+use namada_sdk::tendermint_proto::Protobuf;
+use namada_sdk::types::address::Address;
+use namada_sdk::types::token;
+use serde_json::Value;
+
+use crate::database::{AccountBalance, AccountBalances, Database, Message, TokenTransfer, TxFee};
+use crate::modules::ModuleBasic;
+use crate::node::Node;
+use crate::Error;
+
+// Messages moving native tokens in or out of the balance of the delegator
+const STAKING_MESSAGE_TYPES: &[&str] = &["tx_bond", "tx_withdraw", "tx_claim_rewards"];
+
+#[derive(Clone)]
+pub struct BankModule {
+    node: Node,
+    db: Database,
+    native_token: Arc<Mutex<Option<Address>>>,
+    denominations: Arc<Mutex<HashMap<Address, u8>>>,
+
+    // (owner, token) pairs whose balance changed, by height
+    touched_balances: Arc<Mutex<HashMap<u64, HashSet<(Address, Address)>>>>,
+}
+
+impl BankModule {
+    pub fn new(node: Node, db: Database) -> Self {
+        Self {
+            node,
+            db,
+            native_token: Arc::new(Mutex::new(None)),
+            denominations: Arc::new(Mutex::new(HashMap::new())),
+            touched_balances: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Queries and saves the balances touched by the messages and fees of the block.
+    pub async fn handle_block(&self, height: u64) -> Result<(), Error> {
+        let touched = self
+            .touched_balances
+            .lock()
+            .unwrap()
+            .remove(&height)
+            .unwrap_or_default();
+        if touched.is_empty() {
+            return Ok(());
+        }
+
+        let tokens = touched.iter().map(|(_, token)| token.clone()).collect();
+        let denominations = self.denominations(tokens).await?;
+        let balances = self
+            .node
+            .balances(height, touched.into_iter().collect())
+            .await?
+            .into_iter()
+            .map(|(owner, token, amount)| {
+                let denomination = denominations.get(&token).copied().unwrap_or_default();
+                AccountBalance::new(owner.encode(), token.encode(), amount, denomination, height)
+            })
+            .collect::<Vec<_>>();
+        AccountBalances::from(balances).save(&self.db).await?;
+
+        Ok(())
+    }
+
+    pub fn handle_fee(&self, height: u64, fee: &TxFee) -> Result<(), Error> {
+        let payer = Address::decode(&fee.payer)?;
+        let token = Address::decode(&fee.token)?;
+        self.touch_balance(height, payer, token);
+
+        Ok(())
+    }
+
+    fn touch_balance(&self, height: u64, owner: Address, token: Address) {
+        self.touched_balances
+            .lock()
+            .unwrap()
+            .entry(height)
+            .or_default()
+            .insert((owner, token));
+    }
+
+    fn touch_ibc_sender(&self, height: u64, msg: &MsgTransfer) -> Result<(), Error> {
+        let sender = Address::decode(msg.packet_data.sender.to_string())?;
+        let token = ibc_token_address(&msg.packet_data.token.denom.to_string());
+        self.touch_balance(height, sender, token);
+
+        Ok(())
+    }
+
+    // The receiver of a failed packet may not be a Namada address, it has no balance to touch
+    fn touch_ibc_receiver(&self, height: u64, msg: &MsgRecvPacket, transfer: &TokenTransfer) {
+        let Ok(receiver) = Address::decode(&transfer.target) else {
+            return;
+        };
+
+        // Tokens coming back lose the trace prefix added on their way out of Namada,
+        // the others get the trace of the receiving channel
+        let packet = &msg.packet;
+        let source_prefix = format!("{}/{}/", packet.port_id_on_a, packet.chan_id_on_a);
+        let denom = match transfer.token.strip_prefix(&source_prefix) {
+            Some(denom) => denom.to_string(),
+            None => format!("{}/{}/{}", packet.port_id_on_b, packet.chan_id_on_b, transfer.token),
+        };
+        self.touch_balance(height, receiver, ibc_token_address(&denom));
+    }
+
+    // Denominations never change, they are only queried once per token
+    async fn denominations(&self, tokens: HashSet<Address>) -> Result<HashMap<Address, u8>, Error> {
+        let unknown = {
+            let denominations = self.denominations.lock().unwrap();
+            tokens
+                .iter()
+                .filter(|token| !denominations.contains_key(token))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        if !unknown.is_empty() {
+            let queried = self.node.denominations(unknown).await?;
+            self.denominations.lock().unwrap().extend(queried);
+        }
+
+        let denominations = self.denominations.lock().unwrap();
+        Ok(tokens
+            .into_iter()
+            .filter_map(|token| denominations.get(&token).map(|d| (token, *d)))
+            .collect())
+    }
+
+    async fn native_token(&self) -> Result<Address, Error> {
+        if let Some(native_token) = self.native_token.lock().unwrap().clone() {
+            return Ok(native_token);
+        }
+
+        let native_token = self.node.native_token().await?;
+        *self.native_token.lock().unwrap() = Some(native_token.clone());
+        Ok(native_token)
+    }
+}
+
+impl ModuleBasic for BankModule {
+    fn register_periodic_operations(&self, _scheduler: &mut Scheduler) {}
+
+    async fn handle_epoch(&self, _height: u64, _epoch: Epoch) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn handle_message(&self, message: Message) -> Result<(), Error> {
+        let height = message.height as u64;
+        if STAKING_MESSAGE_TYPES.contains(&message.message_type.as_str()) {
+            let native_token = self.native_token().await?;
+            for (address, role) in message.addresses.iter() {
+                if role == "delegator" {
+                    self.touch_balance(height, Address::decode(address)?, native_token.clone());
+                }
+            }
+        }
+
+        let transfer = match message.message_type.as_str() {
+            "tx_transfer" => {
+                let msg = serde_json::from_value::<token::Transfer>(message.value)?;
+                self.touch_balance(height, msg.source.clone(), msg.token.clone());
+                self.touch_balance(height, msg.target.clone(), msg.token.clone());
+                Some(TokenTransfer::from_transfer(
+                    message.tx_hash,
+                    message.height,
+                    &msg,
+                ))
+            }
+            "tx_ibc.MsgTransfer" => {
+                let msg = MsgTransfer::decode_vec(&any_value(message.value)?)
+                    .map_err(|_| ibc_decode_error(&message.message_type))?;
+                self.touch_ibc_sender(height, &msg)?;
+                Some(TokenTransfer::from_ibc_transfer(
+                    message.tx_hash,
+                    message.height,
+                    &msg,
+                    false,
+                ))
+            }
+            "tx_ibc.MsgShieldedTransfer" => {
+                let msg = MsgTransfer::decode_vec(&any_value(message.value["message"].clone())?)
+                    .map_err(|_| ibc_decode_error(&message.message_type))?;
+                self.touch_ibc_sender(height, &msg)?;
+                Some(TokenTransfer::from_ibc_transfer(
+                    message.tx_hash,
+                    message.height,
+                    &msg,
+                    true,
+                ))
+            }
+            "tx_ibc.MsgRecvPacket" => {
+                let msg = MsgRecvPacket::decode_vec(&any_value(message.value)?)
+                    .map_err(|_| ibc_decode_error(&message.message_type))?;
+                let transfer = TokenTransfer::from_ibc_packet(
+                    message.tx_hash,
+                    message.height,
+                    &msg.packet.data,
+                );
+                if let Some(transfer) = &transfer {
+                    self.touch_ibc_receiver(height, &msg, transfer);
+                }
+                transfer
+            }
+            _ => None,
+        };
+
+        if let Some(transfer) = transfer {
+            transfer.save(&self.db).await?;
+        }
+        Ok(())
+    }
+}
+
+// IBC messages are stored as the JSON of their protobuf `Any`
+fn any_value(value: Value) -> Result<Vec<u8>, Error> {
+    Ok(serde_json::from_value::<Any>(value)?.value)
+}
+
+// Namada token of an ICS-20 denom, a Namada token address or the IBC token of a trace
+fn ibc_token_address(denom: &str) -> Address {
+    Address::decode(denom).unwrap_or_else(|_| ibc_token(denom))
+}
+
+fn ibc_decode_error(message_type: &str) -> Error {
+    Error::InvalidTxData(format!("failed to decode {}", message_type))
+}
@@ -46,7 +46,7 @@ impl ConsensusModule {
         }
 
         // Save average block time per hour
-        let average_block_time = AverageBlockTime::new(average_block_time, 1);
+        let average_block_time = AverageBlockTime::new(average_block_time, 1)?;
         average_block_time
             .save_average_block_time_per_hour(&self.db)
             .await?;
@@ -81,7 +81,7 @@ impl ConsensusModule {
         }
 
         // Save average block time per day
-        let average_block_time = AverageBlockTime::new(average_block_time, block.height);
+        let average_block_time = AverageBlockTime::new(average_block_time, block.height)?;
         average_block_time
             .save_average_block_time_per_day(&self.db)
             .await?;
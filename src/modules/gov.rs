@@ -10,6 +10,7 @@ use crate::database::{Database, Message};
 use crate::error::Error;
 use crate::modules::ModuleBasic;
 use crate::node::Node;
+use crate::utils;
 
 #[derive(Clone)]
 pub struct GovModule {
@@ -32,10 +33,10 @@ impl GovModule {
                 ProposalTallyResult::new(
                     proposal.id as i64,
                     tally.tally_type,
-                    tally.total_voting_power.to_string(),
-                    tally.total_yay_power.to_string(),
-                    tally.total_nay_power.to_string(),
-                    tally.total_abstain_power.to_string(),
+                    utils::raw_amount(tally.total_voting_power),
+                    utils::raw_amount(tally.total_yay_power),
+                    utils::raw_amount(tally.total_nay_power),
+                    utils::raw_amount(tally.total_abstain_power),
                     height,
                 )
                 .save(&self.db)
@@ -61,10 +62,10 @@ impl ModuleBasic for GovModule {
                 ProposalTallyResult::new(
                     proposal.id as i64,
                     tally.tally_type,
-                    tally.total_voting_power.to_string(),
-                    tally.total_yay_power.to_string(),
-                    tally.total_nay_power.to_string(),
-                    tally.total_abstain_power.to_string(),
+                    utils::raw_amount(tally.total_voting_power),
+                    utils::raw_amount(tally.total_yay_power),
+                    utils::raw_amount(tally.total_nay_power),
+                    utils::raw_amount(tally.total_abstain_power),
                     height,
                 )
                 .save(&self.db)
@@ -139,7 +140,10 @@ impl ModuleBasic for GovModule {
                         Block::block_at_height(&self.db, message.height)
                             .await?
                             .map(|b| b.timestamp)
-                            .expect("Block not found"),
+                            .ok_or(Error::InvalidChainData(format!(
+                                "block {} not found",
+                                message.height
+                            )))?,
                         proposal.voting_start_epoch.into(),
                         proposal.voting_end_epoch.into(),
                         proposal.grace_epoch.into(),
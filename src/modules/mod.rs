@@ -13,6 +13,16 @@ pub use consensus::ConsensusModule;
 mod gov;
 pub use gov::GovModule;
 
+mod bank;
+pub use bank::BankModule;
+
+mod supply;
+pub use supply::SupplyModule;
+
+mod account;
+pub use account::AccountModule;
+
+#[allow(async_fn_in_trait)]
 pub trait ModuleBasic {
     fn register_periodic_operations(&self, scheduler: &mut clokwerk::Scheduler);
     async fn handle_epoch(&self, height: u64, epoch: Epoch) -> Result<(), Error>;
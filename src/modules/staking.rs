@@ -1,5 +1,11 @@
+use clokwerk::{Scheduler, TimeUnits};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use namada_sdk::proof_of_stake::types::ValidatorState;
 use namada_sdk::state::Epoch;
-use tendermint::PublicKey;
+use namada_sdk::tx::data::pos;
+use namada_sdk::types::address::Address;
 
 use crate::database::{self, Database};
 use crate::modules::ModuleBasic;
@@ -7,11 +13,31 @@ use crate::node::Node;
 use crate::utils;
 use crate::Error;
 
+// Validator lifecycle messages, recorded as validator events
+const VALIDATOR_EVENT_TYPES: &[&str] = &[
+    "tx_become_validator",
+    "tx_deactivate_validator",
+    "tx_reactivate_validator",
+    "tx_unjail_validator",
+    "tx_change_validator_commission",
+    "tx_change_validator_metadata",
+    "tx_change_consensus_key",
+];
+
+// Messages changing the stake of their validators
+const STAKE_MESSAGE_TYPES: &[&str] = &["tx_bond", "tx_unbond", "tx_redelegate"];
+
 #[derive(Clone)]
 pub struct StakingModule {
     node: Node,
     db: Database,
-    
+
+    // Validators touched by messages or slashes since the last epoch
+    touched_validators: Arc<Mutex<HashSet<Address>>>,
+    // Validators to refresh until their changes are applied, by last epoch
+    pending_validators: Arc<Mutex<HashMap<Address, u64>>>,
+    // Refresh every validator on the next epoch, set on start and periodically
+    full_refresh: Arc<Mutex<bool>>,
 }
 
 impl StakingModule {
@@ -19,27 +45,79 @@ impl StakingModule {
         Self {
             node,
             db,
+            touched_validators: Arc::new(Mutex::new(HashSet::new())),
+            pending_validators: Arc::new(Mutex::new(HashMap::new())),
+            full_refresh: Arc::new(Mutex::new(true)),
         }
     }
 
-    async fn update_validators(&self, height: u64, epoch: Epoch) -> Result<(), Error> {
-        let validator_infos = self.node.validator_infos(epoch).await?;
+    fn touch_validators(&self, message: &database::Message) -> Result<(), Error> {
+        let mut touched = self.touched_validators.lock().unwrap();
+        for (address, role) in message.addresses.iter() {
+            // validator, source_validator and destination_validator
+            if role.ends_with("validator") {
+                touched.insert(Address::decode(address)?);
+            }
+        }
 
-        // Save infos
-        let validators = validator_infos
-            .clone()
+        Ok(())
+    }
+
+    // Returns the validators to refresh at the epoch. Changes of the previous epoch take effect
+    // at its pipeline epoch, so touched validators are refreshed on every epoch until then.
+    // Nothing is cleared here, see `refreshed`, so a failed epoch is retried as a whole.
+    fn validators_to_refresh(&self) -> (Vec<Address>, HashSet<Address>) {
+        let touched = self.touched_validators.lock().unwrap().clone();
+        let pending = self.pending_validators.lock().unwrap();
+        let validators = touched
+            .iter()
+            .chain(pending.keys())
+            .cloned()
+            .collect::<HashSet<_>>()
             .into_iter()
-            .map(|(address, _, _, commission, _, _)| {
-                database::ValidatorInfo::new(
+            .collect();
+
+        (validators, touched)
+    }
+
+    // Moves the refreshed touched validators to the pending ones until their pipeline epoch,
+    // once every update of the epoch succeeded
+    fn refreshed(&self, epoch: Epoch, pipeline_len: u64, touched: HashSet<Address>) {
+        let mut touched_validators = self.touched_validators.lock().unwrap();
+        let mut pending = self.pending_validators.lock().unwrap();
+        let until = epoch.0.saturating_sub(1) + pipeline_len;
+        for address in touched {
+            touched_validators.remove(&address);
+            let last_epoch = pending.entry(address).or_insert(until);
+            *last_epoch = (*last_epoch).max(until);
+        }
+
+        pending.retain(|_, last_epoch| *last_epoch > epoch.0);
+    }
+
+    async fn update_validators(
+        &self,
+        height: u64,
+        epoch: Epoch,
+        validators: Vec<Address>,
+    ) -> Result<(), Error> {
+        if validators.is_empty() {
+            return Ok(());
+        }
+
+        let validator_infos = self.node.validator_infos(height, epoch, validators).await?;
+
+        // Save infos, a validator without commission has none
+        let mut validators = vec![];
+        for (address, _, _, commission, _, _) in validator_infos.iter() {
+            if let Some(commission) = commission {
+                validators.push(database::ValidatorInfo::new(
                     address.encode(),
-                    commission
-                        .unwrap()
-                        .max_commission_change_per_epoch
-                        .to_string(),
+                    utils::decimal(&commission.max_commission_change_per_epoch)?,
                     height,
-                )
-            })
-            .collect::<Vec<_>>();
+                ));
+            }
+        }
         database::ValidatorInfos::from(validators)
             .save(&self.db)
             .await?;
@@ -51,7 +129,8 @@ impl StakingModule {
             .map(|(address, _, voting_power, _, _, _)| {
                 database::ValidatorVotingPower::new(
                     address.encode(),
-                    voting_power.to_string().parse::<i64>().unwrap(),
+                    voting_power,
+                    epoch.into(),
                     height,
                 )
             })
@@ -61,44 +140,27 @@ impl StakingModule {
             .await?;
 
         // Save commissions
-        let validators_commissions = validator_infos
-            .clone()
-            .into_iter()
-            .map(|(address, _, _, commission, _, _)| {
-                if let Some(commission) = commission {
-                    return Some(database::ValidatorCommission::new(
-                        address.encode(),
-                        commission.commission_rate.to_string(),
-                        height,
-                    ));
-                }
-
-                None
-            })
-            .collect::<Vec<_>>();
+        let mut validators_commissions = vec![];
+        for (address, _, _, commission, _, _) in validator_infos.iter() {
+            if let Some(commission) = commission {
+                validators_commissions.push(Some(database::ValidatorCommission::new(
+                    address.encode(),
+                    utils::decimal(&commission.commission_rate)?,
+                    epoch.into(),
+                    height,
+                )));
+            }
+        }
         database::ValidatorCommissions::from(validators_commissions)
             .save(&self.db)
             .await?;
 
         // Save statuses
-        let validators_statuses = validator_infos
-            .clone()
-            .into_iter()
-            .map(|(address, state, _, _, _, _)| {
-                if let Some(state) = state {
-                    return Some(database::ValidatorStatus::new(
-                        address.encode(),
-                        state,
-                        height,
-                    ));
-                }
-
-                None
-            })
-            .collect::<Vec<_>>();
-        database::ValidatorStatuses::from(validators_statuses)
-            .save(&self.db)
-            .await?;
+        let states = validator_infos
+            .iter()
+            .map(|(address, state, _, _, _, _)| (address.clone(), *state))
+            .collect();
+        self.save_validator_states(height, epoch, states).await?;
 
         // Save descriptions
         let validators_descriptions = validator_infos
@@ -106,11 +168,16 @@ impl StakingModule {
             .into_iter()
             .map(|(address, _, _, _, description, _)| {
                 if let Some(description) = description {
+                    // The validator metadata of the SDK has no name yet
                     return Some(database::ValidatorDescription::new(
                         address.encode(),
+                        None,
                         description.clone().avatar.unwrap_or_default(),
                         description.clone().website.unwrap_or_default(),
                         description.clone().description.unwrap_or_default(),
+                        description.clone().email,
+                        description.clone().discord_handle.unwrap_or_default(),
+                        epoch.into(),
                         height,
                     ));
                 }
@@ -124,13 +191,200 @@ impl StakingModule {
 
         for (address, _, _, _, _, pub_key) in validator_infos {
             if let Some(pub_key) = pub_key {
-                let consensus_key = database::ValidatorConsensusKey::new(
-                    Into::<PublicKey>::into(pub_key).to_bech32(utils::COMMON_PK_HRP),
+                let consensus_key =
+                    database::ValidatorConsensusKey::new(pub_key.into(), address.encode());
+                consensus_key.save(&self.db).await?;
+                consensus_key.save_history(&self.db, epoch.into()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // States change without messages, e.g. between the consensus and below capacity sets or
+    // on liveness jailings, so the validators not refreshed have their states updated as well
+    async fn update_validator_states(
+        &self,
+        height: u64,
+        epoch: Epoch,
+        refreshed: &[Address],
+    ) -> Result<(), Error> {
+        let refreshed = refreshed.iter().map(|a| a.encode()).collect::<HashSet<_>>();
+        let mut validators = vec![];
+        for address in database::ValidatorStatuses::validators(&self.db).await? {
+            if !refreshed.contains(&address) {
+                validators.push(Address::decode(&address)?);
+            }
+        }
+        if validators.is_empty() {
+            return Ok(());
+        }
+
+        let states = self.node.validator_states(epoch, validators).await?;
+        self.save_validator_states(height, epoch, states).await
+    }
+
+    async fn save_validator_states(
+        &self,
+        height: u64,
+        epoch: Epoch,
+        states: Vec<(Address, Option<ValidatorState>)>,
+    ) -> Result<(), Error> {
+        // Save jailings, linked to the evidence of the epoch if any, liveness jailings have none
+        let jailed = database::ValidatorStatuses::jailed_validators(&self.db).await?;
+        let mut jailings = vec![];
+        for (address, state) in states.iter() {
+            let address = address.encode();
+            if !matches!(state, Some(ValidatorState::Jailed)) || jailed.contains(&address) {
+                continue;
+            }
+
+            let infraction_height =
+                database::Evidences::infraction_height(&self.db, &address, epoch.into()).await?;
+            jailings.push(database::ValidatorJailing::new(
+                address,
+                infraction_height,
+                epoch.into(),
+                height,
+            ));
+        }
+        database::ValidatorJailings::from(jailings)
+            .save(&self.db)
+            .await?;
+
+        let validators_statuses = states
+            .into_iter()
+            .map(|(address, state)| {
+                state.map(|state| {
+                    database::ValidatorStatus::new(address.encode(), state, epoch.into(), height)
+                })
+            })
+            .collect::<Vec<_>>();
+        database::ValidatorStatuses::from(validators_statuses)
+            .save(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    // Saves the slashes not indexed yet
+    async fn update_slashes(&self) -> Result<(), Error> {
+        let slashes = self.node.slashes().await?;
+        let saved = database::ValidatorSlashes::saved(&self.db).await?;
+
+        let mut new_slashes = vec![];
+        for (address, validator_slashes) in slashes.iter() {
+            for slash in validator_slashes {
+                let key = (
                     address.encode(),
+                    slash.epoch.0 as i64,
+                    slash.block_height as i64,
                 );
-                consensus_key.save(&self.db).await?;
+                if saved.contains(&key) {
+                    continue;
+                }
+                self.touched_validators
+                    .lock()
+                    .unwrap()
+                    .insert(address.clone());
+
+                let stake = self
+                    .node
+                    .validator_stake(slash.epoch, address.clone())
+                    .await?;
+                new_slashes.push(database::ValidatorSlash::new(
+                    address.encode(),
+                    slash,
+                    stake,
+                ));
             }
         }
+        database::ValidatorSlashes::from(new_slashes)
+            .save(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    // The rewards products of every past epoch are kept in storage, they are saved on full
+    // refreshes only to spare one query per consensus validator on every epoch
+    async fn update_validator_rewards(
+        &self,
+        height: u64,
+        validators: Vec<Address>,
+    ) -> Result<(), Error> {
+        let mut rewards = vec![];
+        for (address, epoch, product) in self.node.rewards_products(validators).await? {
+            rewards.push(database::ValidatorReward::new(
+                address.encode(),
+                utils::decimal(&product)?,
+                epoch.into(),
+                height,
+            ));
+        }
+        database::ValidatorRewards::from(rewards)
+            .save(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn save_validator_event(&self, message: &database::Message) -> Result<(), Error> {
+        let validator = message
+            .addresses
+            .iter()
+            .find(|(_, role)| role == "validator")
+            .map(|(address, _)| address.clone())
+            .ok_or(Error::InvalidTxData(format!(
+                "{} message without validator",
+                message.message_type
+            )))?;
+
+        database::ValidatorEvent::new(
+            message.tx_hash.clone(),
+            validator,
+            message.message_type.trim_start_matches("tx_").to_string(),
+            message.value.clone(),
+            message.height,
+        )
+        .save(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    // Re-queries the bonds and unbonds of a delegator to a validator at the message height
+    async fn update_delegation(
+        &self,
+        height: i64,
+        delegator: Address,
+        validator: Address,
+    ) -> Result<(), Error> {
+        let (bonded, unbonds) = self
+            .node
+            .bonds_and_unbonds(delegator.clone(), validator.clone(), height as u64)
+            .await?;
+
+        database::Delegation::new(delegator.encode(), validator.encode(), bonded, height)
+            .save(&self.db)
+            .await?;
+
+        let unbondings = unbonds
+            .into_iter()
+            .map(|(start, withdraw, amount)| {
+                database::Unbonding::new(
+                    delegator.encode(),
+                    validator.encode(),
+                    amount,
+                    start,
+                    withdraw,
+                    height,
+                )
+            })
+            .collect::<Vec<_>>();
+        database::Unbondings::new(delegator.encode(), validator.encode(), unbondings)
+            .save(&self.db)
+            .await?;
 
         Ok(())
     }
@@ -142,17 +396,152 @@ impl ModuleBasic for StakingModule {
             "Updating validators for epoch {}, it will take seconds",
             epoch
         );
-        self.update_validators(height.into(), epoch).await?;
+
+        self.update_slashes().await?;
+        let pipeline_len = self.node.pipeline_len().await?;
+        let full_refresh = *self.full_refresh.lock().unwrap();
+        let (mut validators, touched) = self.validators_to_refresh();
+        if full_refresh {
+            validators = self.node.validator_addresses(epoch).await?;
+        }
+        self.update_validators(height, epoch, validators.clone()).await?;
+        self.update_validator_states(height, epoch, &validators).await?;
+        database::ValidatorHistories::carry_forward(&self.db, epoch.into(), height).await?;
+        if full_refresh {
+            self.update_validator_rewards(height, validators).await?;
+            *self.full_refresh.lock().unwrap() = false;
+        }
+        self.refreshed(epoch, pipeline_len, touched);
 
         Ok(())
     }
 
-    fn register_periodic_operations(&self, _: &mut clokwerk::Scheduler) {
-        // Do nothing
+    fn register_periodic_operations(&self, scheduler: &mut Scheduler) {
+        // Catch the changes not tracked by messages and save the rewards products
+        let full_refresh = self.full_refresh.clone();
+        scheduler.every(1.day()).run(move || {
+            tracing::info!("Scheduling a refresh of all validators on the next epoch");
+            *full_refresh.lock().unwrap() = true;
+        });
     }
 
-    async fn handle_message(&self, _message: crate::database::Message) -> Result<(), Error> {
-        // Do nothing
+    async fn handle_message(&self, message: crate::database::Message) -> Result<(), Error> {
+        if VALIDATOR_EVENT_TYPES.contains(&message.message_type.as_str()) {
+            self.save_validator_event(&message).await?;
+        }
+        if VALIDATOR_EVENT_TYPES.contains(&message.message_type.as_str())
+            || STAKE_MESSAGE_TYPES.contains(&message.message_type.as_str())
+        {
+            self.touch_validators(&message)?;
+        }
+
+        match message.message_type.as_str() {
+            "tx_bond" => {
+                let msg = serde_json::from_value::<pos::Bond>(message.value)?;
+                let delegator = msg.source.unwrap_or(msg.validator.clone());
+                self.update_delegation(message.height, delegator, msg.validator)
+                    .await?;
+            }
+            "tx_unbond" => {
+                let msg = serde_json::from_value::<pos::Unbond>(message.value)?;
+                let delegator = msg.source.unwrap_or(msg.validator.clone());
+                self.update_delegation(message.height, delegator, msg.validator)
+                    .await?;
+            }
+            "tx_withdraw" => {
+                let msg = serde_json::from_value::<pos::Withdraw>(message.value)?;
+                let delegator = msg.source.unwrap_or(msg.validator.clone());
+                self.update_delegation(message.height, delegator, msg.validator)
+                    .await?;
+            }
+            "tx_redelegate" => {
+                let msg = serde_json::from_value::<pos::Redelegation>(message.value)?;
+                database::Redelegation::new(
+                    message.tx_hash,
+                    msg.owner.encode(),
+                    msg.src_validator.encode(),
+                    msg.dest_validator.encode(),
+                    msg.amount,
+                    message.height,
+                )
+                .save(&self.db)
+                .await?;
+
+                self.update_delegation(message.height, msg.owner.clone(), msg.src_validator)
+                    .await?;
+                self.update_delegation(message.height, msg.owner, msg.dest_validator)
+                    .await?;
+            }
+            // Commission, state and consensus key changes only apply at the pipeline
+            // epoch and are picked up by `update_validators`, metadata is immediate
+            "tx_become_validator" => {
+                let msg = serde_json::from_value::<pos::BecomeValidator>(message.value)?;
+                database::ValidatorConsensusKey::new(msg.consensus_key.into(), msg.address.encode())
+                    .save(&self.db)
+                    .await?;
+                database::ValidatorDescriptionChange::new(
+                    msg.address.encode(),
+                    None,
+                    msg.avatar,
+                    msg.website,
+                    msg.description,
+                    Some(msg.email),
+                    msg.discord_handle,
+                    message.height,
+                )
+                .save(&self.db)
+                .await?;
+            }
+            "tx_change_validator_metadata" => {
+                let msg = serde_json::from_value::<pos::MetaDataChange>(message.value)?;
+                database::ValidatorDescriptionChange::new(
+                    msg.validator.encode(),
+                    None,
+                    msg.avatar,
+                    msg.website,
+                    msg.description,
+                    msg.email,
+                    msg.discord_handle,
+                    message.height,
+                )
+                .save(&self.db)
+                .await?;
+            }
+            "tx_change_consensus_key" => {
+                let msg = serde_json::from_value::<pos::ConsensusKeyChange>(message.value)?;
+                database::ValidatorConsensusKey::new(
+                    msg.consensus_key.into(),
+                    msg.validator.encode(),
+                )
+                .save(&self.db)
+                .await?;
+            }
+            "tx_claim_rewards" => {
+                let msg = serde_json::from_value::<pos::ClaimRewards>(message.value)?;
+
+                // Rewards are claimable until the previous block, the claim leaves none
+                let amount = self
+                    .node
+                    .rewards(
+                        msg.validator.clone(),
+                        msg.source.clone(),
+                        (message.height as u64).saturating_sub(1),
+                    )
+                    .await?;
+                let delegator = msg.source.unwrap_or(msg.validator.clone());
+                database::RewardClaim::new(
+                    message.tx_hash,
+                    delegator.encode(),
+                    msg.validator.encode(),
+                    amount,
+                    message.height,
+                )
+                .save(&self.db)
+                .await?;
+            }
+            _ => {}
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,69 @@
+use clokwerk::Scheduler;
+use namada_sdk::state::Epoch;
+use namada_sdk::types::address::Address;
+
+use crate::database::{AccountBalances, Database, Message, Supplies, Supply};
+use crate::modules::ModuleBasic;
+use crate::node::Node;
+use crate::Error;
+
+#[derive(Clone)]
+pub struct SupplyModule {
+    node: Node,
+    db: Database,
+}
+
+impl SupplyModule {
+    pub fn new(node: Node, db: Database) -> Self {
+        Self { node, db }
+    }
+
+    async fn update_supplies(&self, height: u64, epoch: Epoch) -> Result<(), Error> {
+        let native_token = self.node.native_token().await?;
+
+        // Every token of which a balance has been indexed
+        let mut tokens = vec![native_token.clone()];
+        for token in AccountBalances::tokens(&self.db).await? {
+            let token = Address::decode(&token)?;
+            if token != native_token {
+                tokens.push(token);
+            }
+        }
+
+        let total_bonded = self.node.total_bonded(epoch).await?;
+        let denominations = self.node.denominations(tokens.clone()).await?;
+        let supplies = self
+            .node
+            .total_supplies(height, tokens)
+            .await?
+            .into_iter()
+            .map(|(token, total_supply)| {
+                let bonded = (token == native_token).then_some(total_bonded);
+                let denomination = denominations.get(&token).copied().unwrap_or_default();
+                Supply::new(
+                    token.encode(),
+                    total_supply,
+                    denomination,
+                    bonded,
+                    epoch.into(),
+                    height,
+                )
+            })
+            .collect::<Vec<_>>();
+        Supplies::from(supplies).save(&self.db).await?;
+
+        Ok(())
+    }
+}
+
+impl ModuleBasic for SupplyModule {
+    fn register_periodic_operations(&self, _scheduler: &mut Scheduler) {}
+
+    async fn handle_epoch(&self, height: u64, epoch: Epoch) -> Result<(), Error> {
+        self.update_supplies(height, epoch).await
+    }
+
+    async fn handle_message(&self, _message: Message) -> Result<(), Error> {
+        Ok(())
+    }
+}
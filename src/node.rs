@@ -1,4 +1,7 @@
-use namada_sdk::proof_of_stake::types::{CommissionPair, ValidatorMetaData, ValidatorState};
+use namada_sdk::proof_of_stake::types::{
+    BondsAndUnbondsDetails, CommissionPair, Slash, ValidatorMetaData, ValidatorState,
+};
+use std::collections::{HashMap, HashSet};
 use tendermint::block::Height;
 use tendermint_rpc::{endpoint, Client, HttpClient, Paging};
 
@@ -6,13 +9,23 @@ use tokio::runtime::Handle;
 
 use namada_sdk::governance::storage::proposal::StorageProposal;
 use namada_sdk::governance::utils::ProposalResult;
+use namada_sdk::proof_of_stake::storage_key::{
+    validator_consensus_key_key, validator_rewards_product_key,
+};
+use namada_sdk::queries::{Client as QueryClient, RPC};
 use namada_sdk::rpc;
 use namada_sdk::state::Epoch;
-use namada_sdk::types::address::Address;
+use namada_sdk::types::address::{self, Address};
+use namada_sdk::types::dec::Dec;
 use namada_sdk::types::key::common::PublicKey;
-use namada_sdk::types::token::Amount;
+use namada_sdk::types::storage::{Key, KeySeg};
+use namada_sdk::borsh::BorshDeserialize;
+use namada_sdk::types::token::{self, Amount};
 
 use crate::error::Error;
+use crate::utils;
+
+const TX_ALLOWLIST_KEY: &str = "tx_allowlist";
 
 #[derive(Clone)]
 pub struct Node {
@@ -40,7 +53,7 @@ impl Node {
     ) -> Result<endpoint::block_results::Response, Error> {
         let block_results = self
             .rpc_client
-            .block_results(Height::try_from(height).unwrap())
+            .block_results(Height::try_from(height)?)
             .await?;
         Ok(block_results)
     }
@@ -53,9 +66,24 @@ impl Node {
         Ok(validator_set)
     }
 
+    pub async fn validator_addresses(&self, epoch: Epoch) -> Result<Vec<Address>, Error> {
+        let client = self.clone();
+        let validators = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    rpc::get_all_validators(&client.rpc_client, epoch).await
+                })
+            })
+            .await??;
+
+        Ok(validators.into_iter().collect())
+    }
+
     pub async fn validator_infos(
         &self,
+        height: u64,
         epoch: Epoch,
+        validators: Vec<Address>,
     ) -> Result<
         Vec<(
             Address,
@@ -71,11 +99,6 @@ impl Node {
         let validator_infos = Handle::current()
             .spawn_blocking(move || {
                 Handle::current().block_on(async move {
-                    let validators = rpc::get_all_validators(&client.rpc_client, epoch)
-                        .await?
-                        .into_iter()
-                        .collect::<Vec<_>>();
-
                     let mut validator_infos = vec![];
 
                     // HACK: Query 5 validators at a time, to avoid from crashing the RPC server
@@ -83,21 +106,17 @@ impl Node {
                         let mut tasks = vec![];
 
                         for validator in chunk {
-                            tasks.push(client.query_validator_info(epoch, validator.clone()));
+                            tasks.push(client.query_validator_info(height, epoch, validator.clone()));
                         }
 
                         for result in futures::future::join_all(tasks).await {
-                            match result {
-                                Err(e) => return Err(e),
-                                _ => {}
-                            }
-                            validator_infos.push(result.unwrap());
+                            validator_infos.push(result?);
                         }
 
                         // Sleep for a bit to avoid crashing the RPC server
                         tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
                     }
-                    Ok(validator_infos)
+                    Ok::<_, Error>(validator_infos)
                 })
             })
             .await??;
@@ -107,6 +126,7 @@ impl Node {
 
     async fn query_validator_info(
         &self,
+        height: u64,
         epoch: Epoch,
         addr: Address,
     ) -> Result<
@@ -124,13 +144,116 @@ impl Node {
             rpc::get_validator_state(&self.rpc_client, &addr, Some(epoch)),
             rpc::get_validator_stake(&self.rpc_client, epoch, &addr),
             rpc::query_metadata(&self.rpc_client, &addr, Some(epoch)),
-            rpc::query_validator_consensus_keys(&self.rpc_client, &addr),
+            self.query_validator_consensus_key(height, epoch, &addr),
         );
 
         let (metadata, commission) = metadata?;
         Ok((addr, state?, stake?, commission, metadata, pub_key?))
     }
 
+    // The consensus keys are epoched data stored by the epoch they take effect from, the key at
+    // an epoch is the one of the latest epoch not after it. The storage is read at the height,
+    // as the keys of past epochs are trimmed later on.
+    async fn query_validator_consensus_key(
+        &self,
+        height: u64,
+        epoch: Epoch,
+        addr: &Address,
+    ) -> Result<Option<PublicKey>, Error> {
+        let prefix = validator_consensus_key_key(addr);
+        let response = RPC
+            .shell()
+            .storage_prefix(&self.rpc_client, None, Some(height.into()), false, &prefix)
+            .await?;
+
+        let mut pub_key = None;
+        for prefix_value in response.data {
+            // Only the data sub keys hold keys, the others hold the epoched bookkeeping
+            let segments = &prefix_value.key.segments;
+            if segments.len() < 2 || segments[segments.len() - 2].raw() != "data" {
+                continue;
+            }
+            let from_epoch = Epoch::parse(segments[segments.len() - 1].raw())
+                .map_err(|e| Error::InvalidChainData(e.to_string()))?;
+            if from_epoch > epoch {
+                continue;
+            }
+            match pub_key {
+                Some((latest, _)) if latest >= from_epoch => {}
+                _ => {
+                    pub_key = Some((from_epoch, PublicKey::try_from_slice(&prefix_value.value)?));
+                }
+            }
+        }
+
+        Ok(pub_key.map(|(_, pub_key)| pub_key))
+    }
+
+    // Returns the states of the given validators at the epoch. Consensus and below capacity
+    // validators are found in their validator sets, the others are queried one by one.
+    pub async fn validator_states(
+        &self,
+        epoch: Epoch,
+        validators: Vec<Address>,
+    ) -> Result<Vec<(Address, Option<ValidatorState>)>, Error> {
+        let client = self.clone();
+        let states = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let pos = RPC.vp().pos();
+                    let consensus = pos
+                        .consensus_validator_set(&client.rpc_client, &Some(epoch))
+                        .await?
+                        .into_iter()
+                        .map(|v| v.address)
+                        .collect::<HashSet<_>>();
+                    let below_capacity = pos
+                        .below_capacity_validator_set(&client.rpc_client, &Some(epoch))
+                        .await?
+                        .into_iter()
+                        .map(|v| v.address)
+                        .collect::<HashSet<_>>();
+
+                    let mut states = vec![];
+                    let mut others = vec![];
+                    for validator in validators {
+                        if consensus.contains(&validator) {
+                            states.push((validator, Some(ValidatorState::Consensus)));
+                        } else if below_capacity.contains(&validator) {
+                            states.push((validator, Some(ValidatorState::BelowCapacity)));
+                        } else {
+                            others.push(validator);
+                        }
+                    }
+
+                    // HACK: Query 5 validators at a time, to avoid from crashing the RPC server
+                    for chunk in others.chunks(5) {
+                        let mut tasks = vec![];
+
+                        for validator in chunk {
+                            tasks.push(rpc::get_validator_state(
+                                &client.rpc_client,
+                                validator,
+                                Some(epoch),
+                            ));
+                        }
+
+                        let results = futures::future::join_all(tasks).await;
+                        for (validator, state) in chunk.iter().zip(results) {
+                            states.push((validator.clone(), state?));
+                        }
+
+                        // Sleep for a bit to avoid crashing the RPC server
+                        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+                    }
+                    Ok::<_, Error>(states)
+                })
+            })
+            .await??;
+
+        Ok(states)
+    }
+
     pub async fn epoch(&self, height: u64) -> Result<Epoch, Error> {
         let client = self.clone();
         let epoch = Handle::current()
@@ -191,4 +314,362 @@ impl Node {
             Ok(proposal) => Ok(Some(proposal)),
         }
     }
+
+    // Returns the wasm code hashes of the known transaction types, as stored on chain
+    pub async fn tx_codes(&self) -> Result<HashMap<String, String>, Error> {
+        let client = self.clone();
+        let tx_codes = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let mut tx_codes = HashMap::new();
+                    for tx_type in utils::TX_TYPES {
+                        let key = Key::wasm_hash(format!("{}.wasm", tx_type));
+                        let (code_hash, _) =
+                            rpc::query_storage_value_bytes(&client.rpc_client, &key, None, false)
+                                .await?;
+                        if let Some(code_hash) = code_hash {
+                            tx_codes.insert(hex::encode(code_hash), tx_type.to_string());
+                        }
+                    }
+
+                    // Allowlisted codes without a known name are stored as unknown
+                    let allowlist_key = Key::from(address::PARAMETERS.to_db_key())
+                        .push(&TX_ALLOWLIST_KEY.to_owned())
+                        .map_err(|e| Error::InvalidChainData(e.to_string()))?;
+                    let allowlist = rpc::query_storage_value::<_, Vec<String>>(
+                        &client.rpc_client,
+                        &allowlist_key,
+                    )
+                    .await?;
+                    for code_hash in allowlist {
+                        let code_hash = code_hash.to_lowercase();
+                        if !tx_codes.contains_key(&code_hash) {
+                            tracing::warn!("Allowlisted tx code {} has no known name", code_hash);
+                            tx_codes.insert(code_hash, utils::UNKNOWN_TX_TYPE.to_string());
+                        }
+                    }
+
+                    Ok::<_, Error>(tx_codes)
+                })
+            })
+            .await??;
+
+        Ok(tx_codes)
+    }
+
+    pub async fn native_token(&self) -> Result<Address, Error> {
+        let client = self.clone();
+        let native_token = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    rpc::query_native_token(&client.rpc_client).await
+                })
+            })
+            .await??;
+
+        Ok(native_token)
+    }
+
+    // Returns the balances of the given (owner, token) pairs at the given height,
+    // a missing balance is zero
+    pub async fn balances(
+        &self,
+        height: u64,
+        balances: Vec<(Address, Address)>,
+    ) -> Result<Vec<(Address, Address, Amount)>, Error> {
+        let client = self.clone();
+        let balances = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let mut amounts = vec![];
+                    for (owner, token) in balances {
+                        let key = token::balance_key(&token, &owner);
+                        let (value, _) = rpc::query_storage_value_bytes(
+                            &client.rpc_client,
+                            &key,
+                            Some(height.into()),
+                            false,
+                        )
+                        .await?;
+                        let amount = match value {
+                            Some(value) => Amount::try_from_slice(&value)?,
+                            None => Amount::zero(),
+                        };
+                        amounts.push((owner, token, amount));
+                    }
+
+                    Ok::<_, Error>(amounts)
+                })
+            })
+            .await??;
+
+        Ok(balances)
+    }
+
+    // Returns the minted amount of the given tokens at the given height
+    pub async fn total_supplies(
+        &self,
+        height: u64,
+        tokens: Vec<Address>,
+    ) -> Result<Vec<(Address, Amount)>, Error> {
+        let client = self.clone();
+        let supplies = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let mut supplies = vec![];
+                    for token in tokens {
+                        let key = token::minted_balance_key(&token);
+                        let (value, _) = rpc::query_storage_value_bytes(
+                            &client.rpc_client,
+                            &key,
+                            Some(height.into()),
+                            false,
+                        )
+                        .await?;
+                        let amount = match value {
+                            Some(value) => Amount::try_from_slice(&value)?,
+                            None => Amount::zero(),
+                        };
+                        supplies.push((token, amount));
+                    }
+
+                    Ok::<_, Error>(supplies)
+                })
+            })
+            .await??;
+
+        Ok(supplies)
+    }
+
+    // Returns the denominations of the given tokens, IBC tokens have none in storage and
+    // their amounts are already in the base unit of their denom
+    pub async fn denominations(&self, tokens: Vec<Address>) -> Result<HashMap<Address, u8>, Error> {
+        let client = self.clone();
+        let denominations = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let mut denominations = HashMap::new();
+                    for token in tokens {
+                        let key = token::denom_key(&token);
+                        let (value, _) =
+                            rpc::query_storage_value_bytes(&client.rpc_client, &key, None, false)
+                                .await?;
+                        let denomination = match value {
+                            Some(value) => token::Denomination::try_from_slice(&value)?.0,
+                            None => 0,
+                        };
+                        denominations.insert(token, denomination);
+                    }
+
+                    Ok::<_, Error>(denominations)
+                })
+            })
+            .await??;
+
+        Ok(denominations)
+    }
+
+    pub async fn total_bonded(&self, epoch: Epoch) -> Result<Amount, Error> {
+        let client = self.clone();
+        let total_bonded = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    rpc::get_total_staked_tokens(&client.rpc_client, epoch).await
+                })
+            })
+            .await??;
+
+        Ok(total_bonded)
+    }
+
+    // Number of blocks over which the liveness of validators is checked
+    pub async fn liveness_window(&self) -> Result<u64, Error> {
+        let client = self.clone();
+        let params = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    rpc::get_pos_params(&client.rpc_client).await
+                })
+            })
+            .await??;
+
+        Ok(params.owned.liveness_window_check)
+    }
+
+    // Number of epochs after which bonds and validator changes take effect
+    pub async fn pipeline_len(&self) -> Result<u64, Error> {
+        let client = self.clone();
+        let params = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    rpc::get_pos_params(&client.rpc_client).await
+                })
+            })
+            .await??;
+
+        Ok(params.owned.pipeline_len)
+    }
+
+    // Returns the bonded amount of a delegator to a validator, net of slashes, and its
+    // unbonds as (start epoch, withdrawable epoch, amount), in the state of the given height
+    pub async fn bonds_and_unbonds(
+        &self,
+        delegator: Address,
+        validator: Address,
+        height: u64,
+    ) -> Result<(Amount, Vec<(Epoch, Epoch, Amount)>), Error> {
+        let client = self.clone();
+        let details = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let response = QueryClient::request(
+                        &client.rpc_client,
+                        format!("/vp/pos/bonds_and_unbonds/{}/{}", delegator, validator),
+                        None,
+                        Some(height.into()),
+                        false,
+                    )
+                    .await?;
+
+                    Ok::<_, Error>(BondsAndUnbondsDetails::try_from_slice(&response.data)?)
+                })
+            })
+            .await??;
+
+        let mut bonded = Amount::zero();
+        let mut unbonds = vec![];
+        for detail in details.into_values() {
+            for bond in detail.bonds {
+                let slashed = bond.slashed_amount.unwrap_or_default();
+                bonded += bond.amount.checked_sub(slashed).unwrap_or_default();
+            }
+            for unbond in detail.unbonds {
+                let slashed = unbond.slashed_amount.unwrap_or_default();
+                let amount = unbond.amount.checked_sub(slashed).unwrap_or_default();
+                unbonds.push((unbond.start, unbond.withdraw, amount));
+            }
+        }
+
+        Ok((bonded, unbonds))
+    }
+
+    // Returns the rewards claimable by a delegator, or by the validator itself when no
+    // delegator is given, in the state of the given height
+    pub async fn rewards(
+        &self,
+        validator: Address,
+        delegator: Option<Address>,
+        height: u64,
+    ) -> Result<Amount, Error> {
+        let client = self.clone();
+        let rewards = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let path = match delegator {
+                        Some(delegator) => format!("/vp/pos/rewards/{}/{}", validator, delegator),
+                        None => format!("/vp/pos/rewards/{}", validator),
+                    };
+                    let response = QueryClient::request(
+                        &client.rpc_client,
+                        path,
+                        None,
+                        Some(height.into()),
+                        false,
+                    )
+                    .await?;
+
+                    Ok::<_, Error>(Amount::try_from_slice(&response.data)?)
+                })
+            })
+            .await??;
+
+        Ok(rewards)
+    }
+
+    // Returns the rewards products of the given validators over all the epochs kept in storage,
+    // with one prefix query per validator. The rewards product of an epoch is the reward rate
+    // of the bonds to the validator, commission excluded, and only consensus validators earn one.
+    pub async fn rewards_products(
+        &self,
+        validators: Vec<Address>,
+    ) -> Result<Vec<(Address, Epoch, Dec)>, Error> {
+        let client = self.clone();
+        let products = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    let mut products = vec![];
+
+                    // HACK: Query 5 validators at a time, to avoid from crashing the RPC server
+                    for chunk in validators.chunks(5) {
+                        let mut tasks = vec![];
+
+                        for validator in chunk {
+                            tasks.push(client.validator_rewards_products(validator.clone()));
+                        }
+
+                        for result in futures::future::join_all(tasks).await {
+                            products.extend(result?);
+                        }
+
+                        // Sleep for a bit to avoid crashing the RPC server
+                        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+                    }
+                    Ok::<_, Error>(products)
+                })
+            })
+            .await??;
+
+        Ok(products)
+    }
+
+    async fn validator_rewards_products(
+        &self,
+        validator: Address,
+    ) -> Result<Vec<(Address, Epoch, Dec)>, Error> {
+        let prefix = validator_rewards_product_key(&validator);
+        let response = RPC
+            .shell()
+            .storage_prefix(&self.rpc_client, None, None, false, &prefix)
+            .await?;
+
+        // The products are stored by epoch, the last segment of their keys
+        let mut products = vec![];
+        for prefix_value in response.data {
+            let epoch = match prefix_value.key.last() {
+                Some(segment) => Epoch::parse(segment.raw())
+                    .map_err(|e| Error::InvalidChainData(e.to_string()))?,
+                None => continue,
+            };
+            let product = Dec::try_from_slice(&prefix_value.value)?;
+            products.push((validator.clone(), epoch, product));
+        }
+
+        Ok(products)
+    }
+
+    pub async fn slashes(&self) -> Result<HashMap<Address, Vec<Slash>>, Error> {
+        let client = self.clone();
+        let slashes = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    RPC.vp().pos().slashes(&client.rpc_client).await
+                })
+            })
+            .await??;
+
+        Ok(slashes)
+    }
+
+    pub async fn validator_stake(&self, epoch: Epoch, validator: Address) -> Result<Amount, Error> {
+        let client = self.clone();
+        let stake = Handle::current()
+            .spawn_blocking(move || {
+                Handle::current().block_on(async move {
+                    rpc::get_validator_stake(&client.rpc_client, epoch, &validator).await
+                })
+            })
+            .await??;
+
+        Ok(stake)
+    }
 }
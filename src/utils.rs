@@ -1,30 +1,94 @@
+use chrono::{DateTime, Utc};
+use namada_sdk::types::dec::Dec;
 pub use namada_sdk::types::string_encoding::ADDRESS_HRP;
 pub use namada_sdk::types::string_encoding::COMMON_PK_HRP;
+use namada_sdk::types::token::{Amount, NATIVE_MAX_DECIMAL_PLACES};
 use sha2::{Digest, Sha256};
+use sqlx::types::Decimal;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::{env, fs};
-use subtle_encoding::{bech32, hex};
+use subtle_encoding::hex;
+use tendermint::abci::Event;
 use tendermint::account::Id as TmAccountId;
+use tendermint::PublicKey as TmPublicKey;
 use tendermint::validator::Info as ValidatorIfo;
 
 const CHECKSUMS_FILE_PATH_ENV: &str = "CHECKSUMS_FILE_PATH";
 const CHECKSUMS_REMOTE_URL_ENV: &str = "CHECKSUMS_REMOTE_URL";
 const CHECKSUMS_DEFAULT_PATH: &str = "checksums.json";
 
+// Transaction types decoded by `database::parse_tx_to_message`, named after
+// their wasm code.
+pub const TX_TYPES: &[&str] = &[
+    "tx_become_validator",
+    "tx_bond",
+    "tx_bridge_pool",
+    "tx_change_consensus_key",
+    "tx_change_validator_commission",
+    "tx_change_validator_metadata",
+    "tx_claim_rewards",
+    "tx_deactivate_validator",
+    "tx_ibc",
+    "tx_init_account",
+    "tx_init_proposal",
+    "tx_reactivate_validator",
+    "tx_redelegate",
+    "tx_resign_steward",
+    "tx_reveal_pk",
+    "tx_transfer",
+    "tx_unbond",
+    "tx_unjail_validator",
+    "tx_update_account",
+    "tx_update_steward_commission",
+    "tx_vote_proposal",
+    "tx_withdraw",
+];
+
+// Type of the transactions whose code has no known name
+pub const UNKNOWN_TX_TYPE: &str = "unknown";
+
+// Both variables accept a comma separated list, so the checksums of several
+// Namada versions can be loaded at once, e.g. around a protocol upgrade.
 pub fn load_checksums() -> Result<HashMap<String, String>, crate::Error> {
     let checksums_file_path = env::var(CHECKSUMS_FILE_PATH_ENV);
     let checksums_remote_url = env::var(CHECKSUMS_REMOTE_URL_ENV);
 
-    let checksums = match (checksums_file_path, checksums_remote_url) {
-        (Ok(path), _) => fs::read_to_string(path)?,
-        (_, Ok(url)) => ureq::get(&url)
-            .call()
-            .map_err(|e| crate::Error::Generic(Box::new(e)))?
-            .into_string()?,
-        _ => fs::read_to_string(CHECKSUMS_DEFAULT_PATH)?,
+    let mut sources = vec![];
+    match (checksums_file_path, checksums_remote_url) {
+        (Ok(paths), _) => {
+            for path in paths.split(',') {
+                sources.push(fs::read_to_string(path.trim())?);
+            }
+        }
+        (_, Ok(urls)) => {
+            for url in urls.split(',') {
+                sources.push(
+                    ureq::get(url.trim())
+                        .call()
+                        .map_err(|e| crate::Error::Generic(Box::new(e)))?
+                        .into_string()?,
+                );
+            }
+        }
+        // The checksums file is optional, tx codes are also discovered from the chain
+        _ => match fs::read_to_string(CHECKSUMS_DEFAULT_PATH) {
+            Ok(checksums) => sources.push(checksums),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        },
     };
 
-    let json: serde_json::Value = serde_json::from_str(&checksums)?;
+    let mut checksums_map = HashMap::new();
+    for checksums in sources {
+        checksums_map.extend(parse_checksums(&checksums)?);
+    }
+
+    Ok(checksums_map)
+}
+
+fn parse_checksums(checksums: &str) -> Result<HashMap<String, String>, crate::Error> {
+    let json: serde_json::Value = serde_json::from_str(checksums)?;
     let obj = json.as_object().ok_or(crate::Error::InvalidChecksum)?;
 
     let mut checksums_map = HashMap::new();
@@ -34,7 +98,8 @@ pub fn load_checksums() -> Result<HashMap<String, String>, crate::Error> {
             .as_str()
             .ok_or(crate::Error::InvalidChecksum)?
             .split('.')
-            .collect::<Vec<&str>>()[1];
+            .nth(1)
+            .ok_or(crate::Error::InvalidChecksum)?;
         let type_tx = value.0.split('.').collect::<Vec<&str>>()[0];
 
         checksums_map.insert(hash.to_string(), type_tx.to_string());
@@ -43,8 +108,42 @@ pub fn load_checksums() -> Result<HashMap<String, String>, crate::Error> {
     Ok(checksums_map)
 }
 
-pub fn addr_to_bech32(addr: TmAccountId) -> String {
-    bech32::encode(ADDRESS_HRP, addr.as_bytes())
+// Consensus addresses are kept in the hex encoding used by CometBFT, they are
+// not Namada addresses
+pub fn consensus_address(addr: TmAccountId) -> String {
+    addr.to_string()
+}
+
+pub fn consensus_address_from_pubkey(pub_key: TmPublicKey) -> String {
+    consensus_address(TmAccountId::from(pub_key))
+}
+
+// Token amounts are stored as NUMERIC in the smallest unit of the token, they are
+// bound as strings since they may not fit in 128 bits
+pub fn raw_amount(amount: Amount) -> String {
+    amount.raw_amount().to_string()
+}
+
+// Denomination of the staking amounts, which are all in the native token
+pub const NATIVE_DENOMINATION: i16 = NATIVE_MAX_DECIMAL_PLACES as i16;
+
+pub fn decimal(dec: &Dec) -> Result<Decimal, crate::Error> {
+    parse_decimal(&dec.to_string())
+}
+
+pub fn parse_decimal(value: &str) -> Result<Decimal, crate::Error> {
+    Decimal::from_str(value)
+        .map_err(|_| crate::Error::InvalidChainData(format!("invalid decimal {}", value)))
+}
+
+pub fn decimal_from_f64(value: f64) -> Result<Decimal, crate::Error> {
+    Decimal::from_f64_retain(value)
+        .ok_or(crate::Error::InvalidChainData(format!("invalid decimal {}", value)))
+}
+
+pub fn timestamp(time: tendermint::Time) -> Result<DateTime<Utc>, crate::Error> {
+    DateTime::from_timestamp(time.unix_timestamp(), 0)
+        .ok_or(crate::Error::InvalidChainData(format!("invalid timestamp {}", time)))
 }
 
 pub fn find_validator(validators: Vec<ValidatorIfo>, address: TmAccountId) -> Option<ValidatorIfo> {
@@ -60,3 +159,38 @@ pub fn find_validator(validators: Vec<ValidatorIfo>, address: TmAccountId) -> Op
 pub fn tx_hash(raw_tx: Vec<u8>) -> String {
     String::from_utf8(hex::encode_upper(Sha256::digest(raw_tx))).expect("Invalid UTF-8 sequence")
 }
+
+// Returns the addresses of the accounts initialized by a transaction, as reported in the
+// `inner_tx` result of its events. They are either emitted with the tx result, or at the end
+// of the block with the hash of the tx.
+pub fn initialized_accounts(
+    tx_hash: &str,
+    tx_events: &[Event],
+    block_events: &[Event],
+) -> Vec<String> {
+    let block_events = block_events.iter().filter(|event| {
+        event
+            .attributes
+            .iter()
+            .any(|a| a.key == "hash" && a.value.eq_ignore_ascii_case(tx_hash))
+    });
+
+    let mut addresses = tx_events
+        .iter()
+        .chain(block_events)
+        .flat_map(|event| event.attributes.iter())
+        .filter(|a| a.key == "inner_tx")
+        .filter_map(|a| serde_json::from_str::<serde_json::Value>(&a.value).ok())
+        .flat_map(|inner_tx| {
+            inner_tx["initialized_accounts"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+        })
+        .filter_map(|address| address.as_str().map(|a| a.to_string()))
+        .collect::<Vec<_>>();
+    addresses.sort();
+    addresses.dedup();
+
+    addresses
+}
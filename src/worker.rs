@@ -1,5 +1,6 @@
 use async_channel::Receiver;
 use async_channel::Sender;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use tracing::info;
 
@@ -8,6 +9,7 @@ use namada_sdk::state::Epoch;
 use namada_sdk::tx::data::TxType;
 use namada_sdk::tx::Tx as NamadaTx;
 use tendermint::abci::types::ExecTxResult;
+use tendermint::abci::Event;
 use tendermint::abci::Code;
 use tendermint::block::commit_sig::CommitSig;
 use tendermint::block::Commit;
@@ -15,7 +17,7 @@ use tendermint::validator::Info as ValidatorInfo;
 
 use crate::database;
 use crate::modules::ModuleBasic;
-use crate::modules::{StakingModule, GovModule};
+use crate::modules::{AccountModule, BankModule, GovModule, StakingModule, SupplyModule};
 use crate::node::Node;
 use crate::utils;
 use crate::Error;
@@ -26,13 +28,17 @@ pub struct Context {
     rx: Receiver<u64>,
     node: Node,
     db: database::Database,
-    checksums_map: std::collections::HashMap<String, String>,
+    checksums_map: Arc<Mutex<HashMap<String, String>>>,
     epoch: Arc<Mutex<Option<Epoch>>>,
+    liveness_window: Arc<Mutex<u64>>,
 
     // TODO: use trait object when the Namada RPC provides thread-safe client methods
     //modules: Vec<Box<dyn ModuleBasic>>,
     staking: StakingModule,
     gov: GovModule,
+    bank: BankModule,
+    supply: SupplyModule,
+    account: AccountModule,
 }
 
 impl Context {
@@ -41,19 +47,26 @@ impl Context {
         rx: Receiver<u64>,
         node: Node,
         db: database::Database,
-        checksums_map: std::collections::HashMap<String, String>,
+        checksums_map: HashMap<String, String>,
         staking: StakingModule,
         gov: GovModule,
+        bank: BankModule,
+        supply: SupplyModule,
+        account: AccountModule,
     ) -> Self {
         Context {
             tx,
             rx,
             node,
             db,
-            checksums_map,
+            checksums_map: Arc::new(Mutex::new(checksums_map)),
             epoch: Arc::new(Mutex::new(None)),
+            liveness_window: Arc::new(Mutex::new(0)),
             staking,
             gov,
+            bank,
+            supply,
+            account,
         }
     }
 }
@@ -72,7 +85,7 @@ pub async fn start(ctx: Arc<Context>) -> Result<(), Error> {
     }
 }
 
-async fn process_block(ctx: &Context, height: u64) -> Result<(), Error> {
+pub async fn process_block(ctx: &Context, height: u64) -> Result<(), Error> {
     // Query the node
     let (tm_block_response, tm_block_results_response, tm_validators_response) = tokio::join!(
         ctx.node.block(height),
@@ -84,13 +97,14 @@ async fn process_block(ctx: &Context, height: u64) -> Result<(), Error> {
     let tm_block_results = tm_block_results_response?;
     let tm_validators = tm_validators_response?.validators;
     let txs_results = tm_block_results.txs_results.unwrap_or_default();
+    let block_events = tm_block_results.end_block_events.unwrap_or_default();
 
     // Save validators
     let validators: Vec<_> = tm_validators
         .iter()
         .map(|v| {
             database::Validator::new(
-                utils::addr_to_bech32(v.address.clone()),
+                utils::consensus_address(v.address.clone()),
                 v.pub_key.to_bech32(utils::COMMON_PK_HRP),
             )
         })
@@ -98,26 +112,57 @@ async fn process_block(ctx: &Context, height: u64) -> Result<(), Error> {
     database::Validators::from(validators).save(&ctx.db).await?;
 
     // Save block
-    let block = database::Block::from_tm_block(tm_block.clone(), txs_results.clone());
+    let height = tm_block.header.height.into();
+    let epoch = ctx.node.epoch(height).await?;
+    let block = database::Block::from_tm_block(tm_block.clone(), txs_results.clone(), epoch)?;
     block.save(&ctx.db).await?;
 
+    // Save evidences
+    database::Evidences::from_tm_block(&tm_block)?
+        .save(&ctx.db)
+        .await?;
+
     // Handle epoch for modules
-    let height = tm_block.header.height.into();
-    if let Some(epoch) = update_epoch(ctx, height).await? {
+    let new_epoch = update_epoch(ctx, epoch);
+    if new_epoch {
+        update_tx_codes(ctx).await?;
+        *ctx.liveness_window.lock().unwrap() = ctx.node.liveness_window().await?;
         ctx.staking.handle_epoch(height.into(), epoch).await?;
         ctx.gov.handle_epoch(height.into(), epoch).await?;
+        ctx.supply.handle_epoch(height.into(), epoch).await?;
     }
 
-    // Save commits
+    // Save commits, the last commit is signed by the validator set of the previous height
     if let Some(commit) = tm_block.last_commit {
-        process_commit(ctx, height, commit, tm_validators).await?;
+        let commit_validators = ctx.node.validators(height - 1).await?.validators;
+        process_commit(
+            ctx,
+            height,
+            commit,
+            commit_validators,
+            tm_block.header.time,
+            new_epoch,
+        )
+        .await?;
+    }
+
+    // Count the proposed blocks of the epochs fully indexed so far
+    if new_epoch {
+        database::ValidatorProposedBlocks::update_completed(&ctx.db, epoch).await?;
     }
 
     // Save transactions
     for (i, tx) in tm_block.data.iter().enumerate() {
-        process_tx(ctx, height, txs_results[i].clone(), tx.clone()).await?;
+        let tx_result = txs_results.get(i).ok_or(Error::InvalidChainData(format!(
+            "missing result of tx {} at height {}",
+            i, height
+        )))?;
+        process_tx(ctx, height, tx_result.clone(), &block_events, tx.clone()).await?;
     }
 
+    // Save the balances touched by the transactions
+    ctx.bank.handle_block(height).await?;
+
     info!("Processed {}", height);
     Ok(())
 }
@@ -126,6 +171,7 @@ async fn process_tx(
     ctx: &Context,
     height: u64,
     tx_results: ExecTxResult,
+    block_events: &[Event],
     raw_tx: Vec<u8>,
 ) -> Result<(), Error> {
     let namada_tx: NamadaTx = NamadaTx::try_from(raw_tx.as_slice())
@@ -137,31 +183,124 @@ async fn process_tx(
         TxType::Decrypted(_) => "decrypted",
         TxType::Protocol(_) => "protocol",
     };
+    let fee = match &namada_tx.header.tx_type {
+        TxType::Wrapper(wrapper) => Some(database::TxFee::from_wrapper(wrapper)),
+        _ => None,
+    };
 
     let tx_hash = utils::tx_hash(raw_tx);
     let tx = database::Tx::new(
         tx_hash.clone(),
         height as i64,
         tx_results.code == Code::Ok,
-        String::from_utf8(namada_tx.memo().unwrap_or_default()).expect("Invalid UTF-8 sequence"),
+        String::from_utf8_lossy(&namada_tx.memo().unwrap_or_default()).into_owned(),
         tx_type.into(),
         tx_results.gas_wanted,
         tx_results.gas_used,
         tx_results.log,
+        namada_tx.raw_header_hash().to_string(),
+        fee,
     );
     tx.save(&ctx.db).await?;
+    if let Some(fee) = &tx.fee {
+        ctx.bank.handle_fee(height, fee)?;
+    }
+    database::TxSigners::from_tx(tx_hash.clone(), &namada_tx)
+        .save(&ctx.db)
+        .await?;
 
     if !tx.success {
         return Ok(());
     }
 
     // Save message
-    let msg = database::Message::from_tx(&ctx.checksums_map, height as i64, tx_hash, namada_tx);
-    if let Some(msg) = msg {
+    let msg = {
+        let checksums_map = ctx.checksums_map.lock().unwrap();
+        database::Message::from_tx(&checksums_map, height as i64, tx_hash, namada_tx)
+    };
+    if let Some(mut msg) = msg {
+        add_initialized_accounts(&mut msg, &tx_results.events, block_events);
         msg.save(&ctx.db).await?;
+        handle_message(ctx, msg).await?;
+    }
+
+    Ok(())
+}
+
+// Established accounts are only known from the result of the transaction initializing them
+fn add_initialized_accounts(
+    msg: &mut database::Message,
+    tx_events: &[Event],
+    block_events: &[Event],
+) {
+    for address in utils::initialized_accounts(&msg.tx_hash, tx_events, block_events) {
+        msg.addresses.push((address, "initialized_account".to_string()));
+    }
+}
+
+async fn handle_message(ctx: &Context, msg: database::Message) -> Result<(), Error> {
+    // Handle message for modules
+    ctx.staking.handle_message(msg.clone()).await?;
+    ctx.gov.handle_message(msg.clone()).await?;
+    ctx.bank.handle_message(msg.clone()).await?;
+    ctx.account.handle_message(msg).await?;
+
+    Ok(())
+}
 
-        // Handle message for modules
-        ctx.gov.handle_message(msg).await?;
+// Decodes again the stored messages of the given type with the current decoders and
+// checksums, re-fetching their transactions from the node.
+pub async fn reparse_messages(
+    ctx: &Context,
+    message_type: Option<String>,
+    from_height: u64,
+    to_height: u64,
+) -> Result<(), Error> {
+    let tx_hashes = database::Message::tx_hashes_in_range(
+        &ctx.db,
+        message_type,
+        from_height as i64,
+        to_height as i64,
+    )
+    .await?;
+
+    let mut heights: Vec<u64> = tx_hashes.iter().map(|(_, h)| *h as u64).collect();
+    heights.dedup();
+    let tx_hashes: HashSet<String> = tx_hashes.into_iter().map(|(hash, _)| hash).collect();
+
+    for height in heights {
+        let (tm_block_response, tm_block_results_response) =
+            tokio::join!(ctx.node.block(height), ctx.node.block_results(height));
+        let tm_block = tm_block_response?.block;
+        let tm_block_results = tm_block_results_response?;
+        let txs_results = tm_block_results.txs_results.unwrap_or_default();
+        let block_events = tm_block_results.end_block_events.unwrap_or_default();
+
+        for (i, raw_tx) in tm_block.data.iter().enumerate() {
+            let tx_hash = utils::tx_hash(raw_tx.clone());
+            if !tx_hashes.contains(&tx_hash) {
+                continue;
+            }
+
+            let namada_tx: NamadaTx = NamadaTx::try_from(raw_tx.as_slice())
+                .map_err(|_| Error::InvalidTxData("failed to parse raw transaction".into()))?;
+            let msg = {
+                let checksums_map = ctx.checksums_map.lock().unwrap();
+                database::Message::from_tx(&checksums_map, height as i64, tx_hash, namada_tx)
+            };
+            if let Some(mut msg) = msg {
+                let tx_result = txs_results.get(i).ok_or(Error::InvalidChainData(format!(
+                    "missing result of tx {} at height {}",
+                    i, height
+                )))?;
+                add_initialized_accounts(&mut msg, &tx_result.events, &block_events);
+                msg.update(&ctx.db).await?;
+                handle_message(ctx, msg).await?;
+            }
+        }
+        ctx.bank.handle_block(height).await?;
+
+        info!("Reparsed messages at {}", height);
     }
 
     Ok(())
@@ -172,11 +311,13 @@ async fn process_commit(
     height: u64,
     commit: Commit,
     validators: Vec<ValidatorInfo>,
+    block_time: tendermint::Time,
+    new_epoch: bool,
 ) -> Result<(), Error> {
     let mut pre_commits: Vec<database::PreCommit> = vec![];
 
-    for commit_sig in commit.signatures {
-        match commit_sig {
+    for (i, commit_sig) in commit.signatures.into_iter().enumerate() {
+        let (validator_address, timestamp, flag) = match commit_sig {
             CommitSig::BlockIdFlagCommit {
                 validator_address,
                 timestamp,
@@ -186,31 +327,73 @@ async fn process_commit(
                     continue;
                 }
 
-                pre_commits.push(database::PreCommit::from_tm_commit_sig(
-                    height,
-                    validator_address,
-                    validators.clone(),
-                    timestamp,
-                ));
+                (validator_address, timestamp, database::FLAG_COMMIT)
             }
-            _ => {}
-        }
+            CommitSig::BlockIdFlagNil {
+                validator_address,
+                timestamp,
+                ..
+            } => (validator_address, timestamp, database::FLAG_NIL),
+            // Absent signatures are ordered as the validator set
+            CommitSig::BlockIdFlagAbsent => match validators.get(i) {
+                Some(validator) => (validator.address, block_time, database::FLAG_ABSENT),
+                None => continue,
+            },
+        };
+
+        pre_commits.push(database::PreCommit::from_tm_commit_sig(
+            height,
+            validator_address,
+            validators.clone(),
+            timestamp,
+            flag,
+        )?);
     }
 
+    let consensus_addresses = pre_commits
+        .iter()
+        .map(|p| p.consensus_address.clone())
+        .collect::<Vec<_>>();
     database::PreCommits::from(pre_commits)
         .save(&ctx.db)
         .await?;
 
+    // Update uptimes over the liveness window, block by block within an epoch
+    let window = *ctx.liveness_window.lock().unwrap();
+    if new_epoch {
+        database::ValidatorUptimes::recompute(&ctx.db, consensus_addresses, window, height)
+            .await?;
+    } else {
+        database::ValidatorUptimes::update(&ctx.db, window, height).await?;
+    }
+
     Ok(())
 }
 
-async fn update_epoch(ctx: &Context, height: u64) -> Result<Option<Epoch>, Error> {
-    let epoch = ctx.node.epoch(height.into()).await?;
+// Returns whether the epoch is newer than the last handled one
+fn update_epoch(ctx: &Context, epoch: Epoch) -> bool {
     let mut current_epoch = ctx.epoch.lock().unwrap();
     if Some(epoch) <= *current_epoch {
-        return Ok(None);
+        return false;
     }
 
     *current_epoch = Some(epoch);
-    Ok(Some(epoch))
+    true
+}
+
+async fn update_tx_codes(ctx: &Context) -> Result<(), Error> {
+    // Codes of a new protocol version are added to the ones already known
+    let tx_codes = ctx.node.tx_codes().await?;
+    database::TxCodes::from(tx_codes.clone())
+        .save(&ctx.db)
+        .await?;
+
+    // An unknown code never replaces a name, e.g. one of the checksums file
+    let mut checksums_map = ctx.checksums_map.lock().unwrap();
+    for (code_hash, tx_type) in tx_codes {
+        if tx_type != utils::UNKNOWN_TX_TYPE || !checksums_map.contains_key(&code_hash) {
+            checksums_map.insert(code_hash, tx_type);
+        }
+    }
+    Ok(())
 }
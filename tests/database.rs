@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use namada_sdk::proof_of_stake::types::{Slash, SlashType};
+use namada_sdk::state::Epoch;
+use namada_sdk::types::dec::Dec;
+use namada_sdk::types::token::Amount;
+use namadajuno::database::{
+    Database, Evidences, TxCodes, ValidatorConsensusKey, ValidatorProposedBlocks, ValidatorSlash,
+    ValidatorSlashes, ValidatorUptimes, ValidatorVotingPower, ValidatorVotingPowers,
+};
+
+mod common;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn tx_codes_accumulate_checksums_of_every_version() {
+    let db = common::setup_database("tx_codes").await;
+
+    let v1 = HashMap::from([
+        ("aa".to_string(), "tx_transfer".to_string()),
+        ("bb".to_string(), "tx_bond".to_string()),
+    ]);
+    let v2 = HashMap::from([
+        ("cc".to_string(), "tx_transfer".to_string()),
+        ("bb".to_string(), "tx_bond".to_string()),
+    ]);
+    TxCodes::from(v1).save(&db).await.unwrap();
+    TxCodes::from(v2).save(&db).await.unwrap();
+
+    let checksums = TxCodes::load(&db).await.unwrap();
+    assert_eq!(
+        checksums,
+        HashMap::from([
+            ("aa".to_string(), "tx_transfer".to_string()),
+            ("bb".to_string(), "tx_bond".to_string()),
+            ("cc".to_string(), "tx_transfer".to_string()),
+        ])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn validator_voting_powers_keep_one_history_row_per_epoch() {
+    let db = common::setup_database("voting_power_history").await;
+
+    let voting_power = |power: u64, epoch: u64, height: u64| {
+        ValidatorVotingPowers::from(vec![ValidatorVotingPower::new(
+            "tnam1validator".to_string(),
+            Amount::native_whole(power),
+            epoch,
+            height,
+        )])
+    };
+    voting_power(10, 1, 10).save(&db).await.unwrap();
+    voting_power(20, 2, 20).save(&db).await.unwrap();
+    // Above i64::MAX once expressed in the smallest unit
+    voting_power(u64::MAX, 2, 21).save(&db).await.unwrap();
+
+    let latest: (String, i64) =
+        sqlx::query_as("SELECT voting_power::TEXT, height FROM validator_voting_power")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(latest, ("18446744073709551615000000".to_string(), 21));
+
+    let history: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT voting_power::TEXT, epoch FROM validator_voting_power_history ORDER BY epoch",
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        history,
+        vec![("10000000".to_string(), 1), ("20000000".to_string(), 2)]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn consensus_key_history_closes_the_previous_key_on_rotation() {
+    let db = common::setup_database("consensus_key_history").await;
+
+    let validator = common::test_address(1).encode();
+    let old_key = ValidatorConsensusKey::new(common::test_public_key(2).into(), validator.clone());
+    let new_key = ValidatorConsensusKey::new(common::test_public_key(3).into(), validator.clone());
+    old_key.save_history(&db, 1).await.unwrap();
+    old_key.save_history(&db, 2).await.unwrap();
+    new_key.save_history(&db, 3).await.unwrap();
+
+    let history: Vec<(String, i64, Option<i64>)> = sqlx::query_as(
+        r#"SELECT consensus_address, from_epoch, to_epoch
+        FROM validator_consensus_key_history ORDER BY from_epoch"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        history,
+        vec![
+            (old_key.consensus_address.clone(), 1, Some(2)),
+            (new_key.consensus_address.clone(), 3, None),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn proposed_blocks_are_compared_to_the_voting_power_share() {
+    let db = common::setup_database("proposed_blocks").await;
+
+    let validator = common::test_address(1).encode();
+    let key = ValidatorConsensusKey::new(common::test_public_key(2).into(), validator.clone());
+    key.save(&db).await.unwrap();
+    let other = "0000000000000000000000000000000000000000".to_string();
+
+    // Four blocks in epoch 1 with a 3:1 voting power split, one block in epoch 2. The
+    // pre-commits of a block are saved at the next height.
+    for height in 1..=5_i64 {
+        let epoch = if height <= 4 { 1_i64 } else { 2 };
+        let proposer = if height == 4 { &other } else { &key.consensus_address };
+        sqlx::query(
+            r#"INSERT INTO block (height, hash, proposer_address, timestamp, epoch)
+            VALUES ($1, $1::TEXT, $2, NOW(), $3)"#,
+        )
+        .bind(height)
+        .bind(proposer)
+        .bind(epoch)
+        .execute(&db.pool())
+        .await
+        .unwrap();
+        for (address, power) in [(&key.consensus_address, 3_i64), (&other, 1)] {
+            sqlx::query(
+                r#"INSERT INTO pre_commit (consensus_address, height, timestamp, voting_power, proposer_priority, flag)
+                VALUES ($1, $2, NOW(), $3, 0, 'commit')"#,
+            )
+            .bind(address)
+            .bind(height)
+            .bind(power)
+            .execute(&db.pool())
+            .await
+            .unwrap();
+        }
+    }
+
+    // Epochs further back than the lookback are not scanned anymore
+    ValidatorProposedBlocks::update_completed(&db, Epoch(4)).await.unwrap();
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM validator_proposed_blocks")
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+
+    ValidatorProposedBlocks::update_completed(&db, Epoch(2)).await.unwrap();
+
+    // Epoch 2 has not ended yet and is left for later
+    let proposed: Vec<(String, Option<String>, i64, String)> = sqlx::query_as(
+        r#"SELECT consensus_address, validator_address, count, ROUND(expected_count, 2)::TEXT
+        FROM validator_proposed_blocks ORDER BY count DESC"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        proposed,
+        vec![
+            (key.consensus_address.clone(), Some(validator), 3, "3.00".to_string()),
+            (other.clone(), None, 1, "1.00".to_string()),
+        ]
+    );
+
+    // Epoch 2 ends at height 6, it waits for the pre-commits of its last block
+    sqlx::query(
+        r#"INSERT INTO block (height, hash, proposer_address, timestamp, epoch)
+        VALUES (6, '6', $1, NOW(), 3)"#,
+    )
+    .bind(&other)
+    .execute(&db.pool())
+    .await
+    .unwrap();
+    ValidatorProposedBlocks::update_completed(&db, Epoch(3)).await.unwrap();
+    let (count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM validator_proposed_blocks WHERE epoch = 2")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(count, 0);
+
+    for (address, power) in [(&key.consensus_address, 3_i64), (&other, 1)] {
+        sqlx::query(
+            r#"INSERT INTO pre_commit (consensus_address, height, timestamp, voting_power, proposer_priority, flag)
+            VALUES ($1, 6, NOW(), $2, 0, 'commit')"#,
+        )
+        .bind(address)
+        .bind(power)
+        .execute(&db.pool())
+        .await
+        .unwrap();
+    }
+    ValidatorProposedBlocks::update_completed(&db, Epoch(3)).await.unwrap();
+    let proposed: Vec<(String, i64, String)> = sqlx::query_as(
+        r#"SELECT consensus_address, count, ROUND(expected_count, 2)::TEXT
+        FROM validator_proposed_blocks WHERE epoch = 2 ORDER BY count DESC"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        proposed,
+        vec![
+            (key.consensus_address.clone(), 1, "0.75".to_string()),
+            (other, 0, "0.25".to_string()),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn jailings_are_linked_to_evidences_of_the_same_epochs_only() {
+    let db = common::setup_database("jailing_evidence").await;
+
+    let validator = common::test_address(1).encode();
+    let key = ValidatorConsensusKey::new(common::test_public_key(2).into(), validator.clone());
+    key.save(&db).await.unwrap();
+
+    // Evidence of an infraction at height 8 included in a block of epoch 3
+    sqlx::query(
+        r#"INSERT INTO block (height, hash, proposer_address, timestamp, epoch)
+        VALUES (10, 'hash', $1, NOW(), 3)"#,
+    )
+    .bind(&key.consensus_address)
+    .execute(&db.pool())
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"INSERT INTO evidence (height, type, consensus_address, evidence_height, total_voting_power, timestamp, value)
+        VALUES (10, 'duplicate_vote', $1, 8, 100, NOW(), '{}')"#,
+    )
+    .bind(&key.consensus_address)
+    .execute(&db.pool())
+    .await
+    .unwrap();
+
+    let jailed_next_epoch = Evidences::infraction_height(&db, &validator, 4).await.unwrap();
+    assert_eq!(jailed_next_epoch, Some(8));
+
+    // A later jailing, e.g. for liveness, is not linked to the old infraction
+    let jailed_later = Evidences::infraction_height(&db, &validator, 6).await.unwrap();
+    assert_eq!(jailed_later, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn slashed_amounts_are_truncated_to_the_smallest_unit() {
+    let db = common::setup_database("slashed_amounts").await;
+
+    // 1001 * 0.5 is not an integer amount
+    let validator = common::test_address(1).encode();
+    let slash = Slash {
+        epoch: Epoch(1),
+        block_height: 1,
+        r#type: SlashType::DuplicateVote,
+        rate: Dec::from_str("0.5").unwrap(),
+    };
+    ValidatorSlashes::from(vec![ValidatorSlash::new(
+        validator.clone(),
+        &slash,
+        Amount::from_u64(1001),
+    )])
+    .save(&db)
+    .await
+    .unwrap();
+
+    let amounts: Vec<(String, String)> =
+        sqlx::query_as("SELECT validator_address, amount::TEXT FROM validator_slash")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(amounts, vec![(validator, "500".to_string())]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn uptimes_shift_their_window_block_by_block() {
+    let db = common::setup_database("uptime_window").await;
+
+    let address = "0000000000000000000000000000000000000000".to_string();
+    for (height, flag) in [(1_i64, "commit"), (2, "absent"), (3, "commit"), (4, "commit")] {
+        sqlx::query(
+            r#"INSERT INTO pre_commit (consensus_address, height, timestamp, voting_power, proposer_priority, flag)
+            VALUES ($1, $2, NOW(), 1, 0, $3)"#,
+        )
+        .bind(&address)
+        .bind(height)
+        .bind(flag)
+        .execute(&db.pool())
+        .await
+        .unwrap();
+    }
+
+    async fn uptime(db: &Database) -> (i64, i64, i64, i64) {
+        sqlx::query_as("SELECT indexed, signed, missed, height FROM validator_uptime")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap()
+    }
+
+    ValidatorUptimes::recompute(&db, vec![address.clone()], 2, 3).await.unwrap();
+    assert_eq!(uptime(&db).await, (2, 1, 1, 3));
+
+    // Height 4 enters the window and height 2 leaves it
+    ValidatorUptimes::update(&db, 2, 4).await.unwrap();
+    assert_eq!(uptime(&db).await, (2, 2, 0, 4));
+
+    // A window not ending at the previous height is left to the next recompute
+    ValidatorUptimes::update(&db, 2, 6).await.unwrap();
+    assert_eq!(uptime(&db).await, (2, 2, 0, 4));
+}
@@ -0,0 +1,855 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use ibc_proto::cosmos::base::v1beta1::Coin;
+use ibc_proto::google::protobuf::Any;
+use ibc_proto::ibc::applications::transfer::v1::MsgTransfer;
+use ibc_proto::ibc::core::channel::v1 as channel;
+use ibc_proto::ibc::core::client::v1 as client;
+use ibc_proto::ibc::core::commitment::v1::MerklePrefix;
+use ibc_proto::ibc::core::connection::v1 as connection;
+use prost::Message as ProstMessage;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use serde_json::json;
+
+use namada_sdk::account::{InitAccount, UpdateAccount};
+use namada_sdk::borsh::BorshSerialize;
+use namada_sdk::governance::storage::vote::ProposalVote;
+use namada_sdk::governance::{InitProposalData, ProposalType, VoteProposalData};
+use namada_sdk::ibc::apps::transfer::types::msgs::transfer::MsgTransfer as IbcMsgTransfer;
+use namada_sdk::masp_primitives::consensus::{BlockHeight, BranchId};
+use namada_sdk::masp_primitives::transaction::{Authorized, TransactionData, TxVersion};
+use namada_sdk::state::Epoch;
+use namada_sdk::tendermint_proto::Protobuf;
+use namada_sdk::tx::data::{pgf, pos, TxType};
+use namada_sdk::tx::Tx as NamadaTx;
+use namada_sdk::types::address;
+use namada_sdk::types::dec::Dec;
+use namada_sdk::types::eth_bridge_pool::{
+    GasFee, PendingTransfer, TransferToEthereum, TransferToEthereumKind,
+};
+use namada_sdk::types::ethereum_events::EthAddress;
+use namada_sdk::types::hash::Hash;
+use namada_sdk::types::ibc::{IbcShieldedTransfer, MsgShieldedTransfer};
+use namada_sdk::types::token::{Amount, DenominatedAmount, Denomination, Transfer};
+
+use namadajuno::database::parse_tx_to_message;
+use namadajuno::utils::TX_TYPES;
+
+mod common;
+
+const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+
+fn code(tx_type: &str) -> Vec<u8> {
+    format!("{}.wasm", tx_type).into_bytes()
+}
+
+fn checksums() -> HashMap<String, String> {
+    TX_TYPES
+        .iter()
+        .map(|tx_type| (common::code_hash(&code(tx_type)), tx_type.to_string()))
+        .collect()
+}
+
+fn tx(tx_type: &str, data: impl BorshSerialize) -> NamadaTx {
+    common::build_namada_tx(&code(tx_type), data)
+}
+
+fn ibc_tx(msg: impl ProstMessage) -> NamadaTx {
+    common::build_namada_tx_with_raw_data(&code("tx_ibc"), msg.encode_to_vec())
+}
+
+/// Compares the parsed `(type, value, addresses)` of `tx` with `tests/fixtures/messages/<name>.json`.
+///
+/// A missing snapshot fails the test, `UPDATE_GOLDEN=1` writes the missing
+/// snapshots and rewrites the existing ones after an intended change of the output.
+fn assert_golden(name: &str, tx: NamadaTx) {
+    let (message_type, value, addresses) = parse_tx_to_message(&checksums(), tx)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {}", name, e))
+        .unwrap_or_else(|| panic!("{} was not parsed to a message", name));
+    let actual = json!({ "type": message_type, "value": value, "addresses": addresses });
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/messages")
+        .join(format!("{}.json", name));
+    if std::env::var(UPDATE_GOLDEN_ENV).is_ok() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+        return;
+    }
+    assert!(
+        path.exists(),
+        "missing snapshot {}, run with {}=1 to write it",
+        path.display(),
+        UPDATE_GOLDEN_ENV
+    );
+
+    let expected: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(
+        actual, expected,
+        "{} differs from its snapshot, rerun with {}=1 if the change is intended",
+        name, UPDATE_GOLDEN_ENV
+    );
+}
+
+fn amount() -> Amount {
+    Amount::native_whole(100)
+}
+
+fn rate() -> Dec {
+    Dec::new(5, 2).unwrap()
+}
+
+//--------------------------------------------------------
+
+#[test]
+fn golden_tx_become_validator() {
+    assert_golden(
+        "tx_become_validator",
+        tx(
+            "tx_become_validator",
+            pos::BecomeValidator {
+                address: common::test_address(1),
+                consensus_key: common::test_public_key(2),
+                eth_cold_key: common::test_public_key(3),
+                eth_hot_key: common::test_public_key(4),
+                protocol_key: common::test_public_key(5),
+                commission_rate: rate(),
+                max_commission_rate_change: rate(),
+                email: "validator@namada.net".to_string(),
+                description: Some("A validator".to_string()),
+                website: Some("https://namada.net".to_string()),
+                discord_handle: Some("validator".to_string()),
+                avatar: Some("https://namada.net/avatar.png".to_string()),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_bond() {
+    assert_golden(
+        "tx_bond",
+        tx(
+            "tx_bond",
+            pos::Bond {
+                validator: common::test_address(1),
+                amount: amount(),
+                source: Some(common::test_address(2)),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_bridge_pool() {
+    assert_golden(
+        "tx_bridge_pool",
+        tx(
+            "tx_bridge_pool",
+            PendingTransfer {
+                transfer: TransferToEthereum {
+                    kind: TransferToEthereumKind::Erc20,
+                    asset: EthAddress([1; 20]),
+                    recipient: EthAddress([2; 20]),
+                    sender: common::test_address(1),
+                    amount: amount(),
+                },
+                gas_fee: GasFee {
+                    amount: amount(),
+                    payer: common::test_address(1),
+                    token: address::nam(),
+                },
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_change_consensus_key() {
+    assert_golden(
+        "tx_change_consensus_key",
+        tx(
+            "tx_change_consensus_key",
+            pos::ConsensusKeyChange {
+                validator: common::test_address(1),
+                consensus_key: common::test_public_key(2),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_change_validator_commission() {
+    assert_golden(
+        "tx_change_validator_commission",
+        tx(
+            "tx_change_validator_commission",
+            pos::CommissionChange {
+                validator: common::test_address(1),
+                new_rate: rate(),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_change_validator_metadata() {
+    assert_golden(
+        "tx_change_validator_metadata",
+        tx(
+            "tx_change_validator_metadata",
+            pos::MetaDataChange {
+                validator: common::test_address(1),
+                email: Some("validator@namada.net".to_string()),
+                description: None,
+                website: Some("https://namada.net".to_string()),
+                discord_handle: None,
+                avatar: None,
+                commission_rate: Some(rate()),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_claim_rewards() {
+    assert_golden(
+        "tx_claim_rewards",
+        tx(
+            "tx_claim_rewards",
+            pos::ClaimRewards {
+                validator: common::test_address(1),
+                source: Some(common::test_address(2)),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_deactivate_validator() {
+    assert_golden(
+        "tx_deactivate_validator",
+        tx("tx_deactivate_validator", common::test_address(1)),
+    );
+}
+
+#[test]
+fn golden_tx_init_account() {
+    assert_golden(
+        "tx_init_account",
+        tx(
+            "tx_init_account",
+            InitAccount {
+                public_keys: vec![common::test_public_key(1), common::test_public_key(2)],
+                vp_code_hash: Hash::default(),
+                threshold: 1,
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_init_proposal() {
+    assert_golden(
+        "tx_init_proposal",
+        tx(
+            "tx_init_proposal",
+            InitProposalData {
+                id: 1,
+                content: Hash::default(),
+                author: common::test_address(1),
+                r#type: ProposalType::Default(None),
+                voting_start_epoch: Epoch(3),
+                voting_end_epoch: Epoch(6),
+                grace_epoch: Epoch(9),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_reactivate_validator() {
+    assert_golden(
+        "tx_reactivate_validator",
+        tx("tx_reactivate_validator", common::test_address(1)),
+    );
+}
+
+#[test]
+fn golden_tx_redelegate() {
+    assert_golden(
+        "tx_redelegate",
+        tx(
+            "tx_redelegate",
+            pos::Redelegation {
+                src_validator: common::test_address(1),
+                dest_validator: common::test_address(2),
+                owner: common::test_address(3),
+                amount: amount(),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_resign_steward() {
+    assert_golden(
+        "tx_resign_steward",
+        tx("tx_resign_steward", common::test_address(1)),
+    );
+}
+
+#[test]
+fn golden_tx_reveal_pk() {
+    assert_golden(
+        "tx_reveal_pk",
+        tx("tx_reveal_pk", common::test_public_key(1)),
+    );
+}
+
+#[test]
+fn golden_tx_transfer() {
+    assert_golden(
+        "tx_transfer",
+        tx(
+            "tx_transfer",
+            Transfer {
+                source: common::test_address(1),
+                target: common::test_address(2),
+                token: address::nam(),
+                amount: DenominatedAmount::new(amount(), Denomination(6)),
+                key: None,
+                shielded: None,
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_unbond() {
+    assert_golden(
+        "tx_unbond",
+        tx(
+            "tx_unbond",
+            pos::Unbond {
+                validator: common::test_address(1),
+                amount: amount(),
+                source: None,
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_unjail_validator() {
+    assert_golden(
+        "tx_unjail_validator",
+        tx("tx_unjail_validator", common::test_address(1)),
+    );
+}
+
+#[test]
+fn golden_tx_update_account() {
+    assert_golden(
+        "tx_update_account",
+        tx(
+            "tx_update_account",
+            UpdateAccount {
+                addr: common::test_address(1),
+                vp_code_hash: Some(Hash::default()),
+                public_keys: vec![common::test_public_key(2)],
+                threshold: Some(1),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_update_steward_commission() {
+    assert_golden(
+        "tx_update_steward_commission",
+        tx(
+            "tx_update_steward_commission",
+            pgf::UpdateStewardCommission {
+                steward: common::test_address(1),
+                commission: HashMap::from([(common::test_address(2), rate())]),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_vote_proposal() {
+    assert_golden(
+        "tx_vote_proposal",
+        tx(
+            "tx_vote_proposal",
+            VoteProposalData {
+                id: 1,
+                vote: ProposalVote::Yay,
+                voter: common::test_address(1),
+                delegations: vec![common::test_address(2)],
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_tx_withdraw() {
+    assert_golden(
+        "tx_withdraw",
+        tx(
+            "tx_withdraw",
+            pos::Withdraw {
+                validator: common::test_address(1),
+                source: Some(common::test_address(2)),
+            },
+        ),
+    );
+}
+
+#[test]
+fn golden_unknown() {
+    assert_golden(
+        "unknown",
+        common::build_namada_tx(b"tx_custom.wasm", common::test_address(1)),
+    );
+}
+
+#[test]
+fn unknown_message_keeps_code_and_data() {
+    let mut tx = NamadaTx::from_type(TxType::Raw);
+    tx.add_code(b"tx_custom.wasm".to_vec(), Some("tx_custom.wasm".to_string()))
+        .add_serialized_data(vec![0xde, 0xad, 0xbe, 0xef]);
+
+    let (message_type, value, addresses) = parse_tx_to_message(&checksums(), tx).unwrap().unwrap();
+    assert_eq!(message_type, "unknown");
+    assert!(addresses.is_empty());
+    assert_eq!(
+        value,
+        json!({
+            "code_hash": common::code_hash(b"tx_custom.wasm"),
+            "code_tag": "tx_custom.wasm",
+            "data": "deadbeef",
+            "data_length": 4,
+        })
+    );
+}
+
+#[test]
+fn tx_without_code_is_not_parsed() {
+    let tx = NamadaTx::from_type(TxType::Raw);
+    assert!(parse_tx_to_message(&checksums(), tx).unwrap().is_none());
+}
+
+//--------------------------------------------------------
+
+const SIGNER: &str = "tnam1qqr5jxnyu2xl9g8xr3gnm3mf0qr00qehn5k8wfzq";
+
+fn client_any() -> Option<Any> {
+    Some(Any {
+        type_url: "/ibc.lightclients.tendermint.v1.ClientState".to_string(),
+        value: vec![1, 2, 3],
+    })
+}
+
+fn height() -> Option<client::Height> {
+    Some(client::Height {
+        revision_number: 0,
+        revision_height: 10,
+    })
+}
+
+fn proof() -> Vec<u8> {
+    vec![1; 32]
+}
+
+fn connection_counterparty(connection_id: &str) -> Option<connection::Counterparty> {
+    Some(connection::Counterparty {
+        client_id: "07-tendermint-1".to_string(),
+        connection_id: connection_id.to_string(),
+        prefix: Some(MerklePrefix {
+            key_prefix: b"ibc".to_vec(),
+        }),
+    })
+}
+
+fn connection_version() -> connection::Version {
+    connection::Version {
+        identifier: "1".to_string(),
+        features: vec!["ORDER_ORDERED".to_string(), "ORDER_UNORDERED".to_string()],
+    }
+}
+
+fn channel_end(state: channel::State, counterparty_channel_id: &str) -> Option<channel::Channel> {
+    Some(channel::Channel {
+        state: state as i32,
+        ordering: channel::Order::Unordered as i32,
+        counterparty: Some(channel::Counterparty {
+            port_id: "transfer".to_string(),
+            channel_id: counterparty_channel_id.to_string(),
+        }),
+        connection_hops: vec!["connection-0".to_string()],
+        version: "ics20-1".to_string(),
+        ..Default::default()
+    })
+}
+
+fn packet() -> Option<channel::Packet> {
+    Some(channel::Packet {
+        sequence: 1,
+        source_port: "transfer".to_string(),
+        source_channel: "channel-0".to_string(),
+        destination_port: "transfer".to_string(),
+        destination_channel: "channel-1".to_string(),
+        data: br#"{"amount":"100","denom":"nam","receiver":"cosmos1receiver","sender":"tnam1sender"}"#
+            .to_vec(),
+        timeout_height: Some(client::Height {
+            revision_number: 0,
+            revision_height: 1000,
+        }),
+        timeout_timestamp: 0,
+    })
+}
+
+#[test]
+fn golden_tx_ibc_msg_create_client() {
+    assert_golden(
+        "tx_ibc.MsgCreateClient",
+        ibc_tx(client::MsgCreateClient {
+            client_state: client_any(),
+            consensus_state: client_any(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_update_client() {
+    assert_golden(
+        "tx_ibc.MsgUpdateClient",
+        ibc_tx(client::MsgUpdateClient {
+            client_id: "07-tendermint-0".to_string(),
+            client_message: client_any(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_submit_misbehaviour() {
+    #[allow(deprecated)]
+    let msg = client::MsgSubmitMisbehaviour {
+        client_id: "07-tendermint-0".to_string(),
+        misbehaviour: client_any(),
+        signer: SIGNER.to_string(),
+    };
+    assert_golden("tx_ibc.MsgSubmitMisbehaviour", ibc_tx(msg));
+}
+
+#[test]
+fn golden_tx_ibc_msg_upgrade_client() {
+    assert_golden(
+        "tx_ibc.MsgUpgradeClient",
+        ibc_tx(client::MsgUpgradeClient {
+            client_id: "07-tendermint-0".to_string(),
+            client_state: client_any(),
+            consensus_state: client_any(),
+            proof_upgrade_client: proof(),
+            proof_upgrade_consensus_state: proof(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_connection_open_init() {
+    assert_golden(
+        "tx_ibc.MsgConnectionOpenInit",
+        ibc_tx(connection::MsgConnectionOpenInit {
+            client_id: "07-tendermint-0".to_string(),
+            counterparty: connection_counterparty(""),
+            version: Some(connection_version()),
+            delay_period: 0,
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_connection_open_try() {
+    assert_golden(
+        "tx_ibc.MsgConnectionOpenTry",
+        ibc_tx(connection::MsgConnectionOpenTry {
+            client_id: "07-tendermint-0".to_string(),
+            client_state: client_any(),
+            counterparty: connection_counterparty("connection-1"),
+            delay_period: 0,
+            counterparty_versions: vec![connection_version()],
+            proof_height: height(),
+            proof_init: proof(),
+            proof_client: proof(),
+            proof_consensus: proof(),
+            consensus_height: height(),
+            signer: SIGNER.to_string(),
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_connection_open_ack() {
+    assert_golden(
+        "tx_ibc.MsgConnectionOpenAck",
+        ibc_tx(connection::MsgConnectionOpenAck {
+            connection_id: "connection-0".to_string(),
+            counterparty_connection_id: "connection-1".to_string(),
+            version: Some(connection_version()),
+            client_state: client_any(),
+            proof_height: height(),
+            proof_try: proof(),
+            proof_client: proof(),
+            proof_consensus: proof(),
+            consensus_height: height(),
+            signer: SIGNER.to_string(),
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_connection_open_confirm() {
+    assert_golden(
+        "tx_ibc.MsgConnectionOpenConfirm",
+        ibc_tx(connection::MsgConnectionOpenConfirm {
+            connection_id: "connection-0".to_string(),
+            proof_ack: proof(),
+            proof_height: height(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_channel_open_init() {
+    assert_golden(
+        "tx_ibc.MsgChannelOpenInit",
+        ibc_tx(channel::MsgChannelOpenInit {
+            port_id: "transfer".to_string(),
+            channel: channel_end(channel::State::Init, ""),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_channel_open_try() {
+    assert_golden(
+        "tx_ibc.MsgChannelOpenTry",
+        ibc_tx(channel::MsgChannelOpenTry {
+            port_id: "transfer".to_string(),
+            channel: channel_end(channel::State::Tryopen, "channel-1"),
+            counterparty_version: "ics20-1".to_string(),
+            proof_init: proof(),
+            proof_height: height(),
+            signer: SIGNER.to_string(),
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_channel_open_ack() {
+    assert_golden(
+        "tx_ibc.MsgChannelOpenAck",
+        ibc_tx(channel::MsgChannelOpenAck {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            counterparty_channel_id: "channel-1".to_string(),
+            counterparty_version: "ics20-1".to_string(),
+            proof_try: proof(),
+            proof_height: height(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_channel_open_confirm() {
+    assert_golden(
+        "tx_ibc.MsgChannelOpenConfirm",
+        ibc_tx(channel::MsgChannelOpenConfirm {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            proof_ack: proof(),
+            proof_height: height(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_channel_close_init() {
+    assert_golden(
+        "tx_ibc.MsgChannelCloseInit",
+        ibc_tx(channel::MsgChannelCloseInit {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_channel_close_confirm() {
+    assert_golden(
+        "tx_ibc.MsgChannelCloseConfirm",
+        ibc_tx(channel::MsgChannelCloseConfirm {
+            port_id: "transfer".to_string(),
+            channel_id: "channel-0".to_string(),
+            proof_init: proof(),
+            proof_height: height(),
+            signer: SIGNER.to_string(),
+            ..Default::default()
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_recv_packet() {
+    assert_golden(
+        "tx_ibc.MsgRecvPacket",
+        ibc_tx(channel::MsgRecvPacket {
+            packet: packet(),
+            proof_commitment: proof(),
+            proof_height: height(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_acknowledgement() {
+    assert_golden(
+        "tx_ibc.MsgAcknowledgement",
+        ibc_tx(channel::MsgAcknowledgement {
+            packet: packet(),
+            acknowledgement: br#"{"result":"AQ=="}"#.to_vec(),
+            proof_acked: proof(),
+            proof_height: height(),
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_timeout() {
+    assert_golden(
+        "tx_ibc.MsgTimeout",
+        ibc_tx(channel::MsgTimeout {
+            packet: packet(),
+            proof_unreceived: proof(),
+            proof_height: height(),
+            next_sequence_recv: 1,
+            signer: SIGNER.to_string(),
+        }),
+    );
+}
+
+#[test]
+fn golden_tx_ibc_msg_timeout_on_close() {
+    assert_golden(
+        "tx_ibc.MsgTimeoutOnClose",
+        ibc_tx(channel::MsgTimeoutOnClose {
+            packet: packet(),
+            proof_unreceived: proof(),
+            proof_close: proof(),
+            proof_height: height(),
+            next_sequence_recv: 1,
+            signer: SIGNER.to_string(),
+            ..Default::default()
+        }),
+    );
+}
+
+fn ibc_transfer() -> MsgTransfer {
+    MsgTransfer {
+        source_port: "transfer".to_string(),
+        source_channel: "channel-0".to_string(),
+        token: Some(Coin {
+            denom: "nam".to_string(),
+            amount: "100".to_string(),
+        }),
+        sender: SIGNER.to_string(),
+        receiver: "cosmos1receiver".to_string(),
+        timeout_height: Some(client::Height {
+            revision_number: 0,
+            revision_height: 1000,
+        }),
+        timeout_timestamp: 0,
+        memo: String::new(),
+    }
+}
+
+#[test]
+fn golden_tx_ibc_msg_transfer() {
+    assert_golden("tx_ibc.MsgTransfer", ibc_tx(ibc_transfer()));
+}
+
+#[test]
+fn golden_tx_ibc_msg_shielded_transfer() {
+    let message = IbcMsgTransfer::decode_vec(&ibc_transfer().encode_to_vec()).unwrap();
+    // The MASP transaction is not part of the message value, an empty one is enough
+    let masp_tx: TransactionData<Authorized> = TransactionData::from_parts(
+        TxVersion::MASPv5,
+        BranchId::MASP,
+        0,
+        BlockHeight::from_u32(0),
+        None,
+        None,
+    );
+    assert_golden(
+        "tx_ibc.MsgShieldedTransfer",
+        tx(
+            "tx_ibc",
+            MsgShieldedTransfer {
+                message,
+                shielded_transfer: IbcShieldedTransfer {
+                    transfer: Transfer {
+                        source: address::masp(),
+                        target: common::test_address(2),
+                        token: address::nam(),
+                        amount: DenominatedAmount::new(amount(), Denomination(6)),
+                        key: None,
+                        shielded: None,
+                    },
+                    masp_tx: masp_tx.freeze().unwrap(),
+                },
+            },
+        ),
+    );
+}
+
+//--------------------------------------------------------
+
+proptest! {
+    #[test]
+    fn parse_tx_to_message_never_panics_on_arbitrary_data(
+        index in 0..TX_TYPES.len(),
+        data in vec(any::<u8>(), 0..1024),
+    ) {
+        let tx = common::build_namada_tx_with_raw_data(&code(TX_TYPES[index]), data);
+        let _ = parse_tx_to_message(&checksums(), tx);
+    }
+
+    #[test]
+    fn parse_tx_to_message_never_panics_on_arbitrary_txs(raw_tx in vec(any::<u8>(), 0..2048)) {
+        if let Ok(tx) = NamadaTx::try_from(raw_tx.as_slice()) {
+            let _ = parse_tx_to_message(&checksums(), tx);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use namada_sdk::borsh::BorshSerialize;
+use namada_sdk::proof_of_stake::storage::{
+    validator_consensus_key_handle, validator_rewards_products_handle,
+};
+use namada_sdk::proof_of_stake::storage_key::{
+    validator_consensus_key_key, validator_rewards_product_key,
+};
+use namada_sdk::proof_of_stake::types::{PosParams, Slash, ValidatorState, WeightedValidator};
+use namada_sdk::state::Epoch;
+use namada_sdk::types::address::Address;
+use namada_sdk::types::dec::Dec;
+use namada_sdk::types::storage::{Key, PrefixValue};
+use namada_sdk::types::token::Amount;
+use serde_json::json;
+
+use namadajuno::database::Message;
+use namadajuno::modules::{ModuleBasic, StakingModule};
+use namadajuno::utils;
+
+mod common;
+
+use common::MockNode;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_epoch_refreshes_only_touched_validators() {
+    let db = common::setup_database("touched_validators").await;
+    let node = MockNode::start();
+
+    // The untouched validator has no recorded info, refreshing it would fail
+    let touched = common::test_address(1);
+    let untouched = common::test_address(2);
+    node.respond_abci_query("/vp/pos/validator/addresses/1", HashSet::<Address>::new());
+    node.respond_abci_query(
+        "/vp/pos/validator/addresses/2",
+        HashSet::from([touched.clone(), untouched.clone()]),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/stake/{}/2", touched),
+        Some(Amount::native_whole(1_000)),
+    );
+    for query in ["state", "commission", "metadata"] {
+        node.respond_abci_query(&format!("/vp/pos/validator/{}/{}*", query, touched), None::<()>);
+    }
+    node.respond_abci_query("/vp/pos/slashes", HashMap::<Address, Vec<Slash>>::new());
+    node.respond_abci_query("/vp/pos/pos_params", PosParams::default());
+    node.respond_abci_query("/vp/pos/validator_set/*", BTreeSet::<WeightedValidator>::new());
+    node.respond_abci_query("/shell/prefix/*", Vec::<PrefixValue>::new());
+
+    let staking = StakingModule::new(node.node(), db.clone());
+    staking.handle_epoch(1, Epoch(1)).await.unwrap();
+    staking
+        .handle_message(Message {
+            height: 2,
+            tx_hash: "hash".to_string(),
+            message_type: "tx_deactivate_validator".to_string(),
+            value: json!(touched.encode()),
+            code_hash: None,
+            addresses: vec![(touched.encode(), "validator".to_string())],
+        })
+        .await
+        .unwrap();
+    staking.handle_epoch(3, Epoch(2)).await.unwrap();
+
+    let voting_powers: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT validator_address, voting_power::TEXT, epoch FROM validator_voting_power_history",
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(voting_powers, vec![(touched.encode(), "1000000000".to_string(), 2)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn failed_epoch_keeps_validators_to_refresh() {
+    let db = common::setup_database("failed_epoch").await;
+    let node = MockNode::start();
+
+    let validator = common::test_address(1);
+    for query in ["state", "commission", "metadata"] {
+        node.respond_abci_query(&format!("/vp/pos/validator/{}/{}*", query, validator), None::<()>);
+    }
+    node.respond_abci_query("/vp/pos/slashes", HashMap::<Address, Vec<Slash>>::new());
+    node.respond_abci_query("/vp/pos/pos_params", PosParams::default());
+    node.respond_abci_query("/vp/pos/validator_set/*", BTreeSet::<WeightedValidator>::new());
+    node.respond_abci_query("/shell/prefix/*", Vec::<PrefixValue>::new());
+
+    // The validator addresses are missing, the full refresh is retried on the next attempt
+    let staking = StakingModule::new(node.node(), db.clone());
+    assert!(staking.handle_epoch(1, Epoch(1)).await.is_err());
+    node.respond_abci_query("/vp/pos/validator/addresses/1", HashSet::<Address>::new());
+    staking.handle_epoch(1, Epoch(1)).await.unwrap();
+
+    // The stake is missing, the touched validator is kept for the next attempt
+    staking
+        .handle_message(Message {
+            height: 2,
+            tx_hash: "hash".to_string(),
+            message_type: "tx_deactivate_validator".to_string(),
+            value: json!(validator.encode()),
+            code_hash: None,
+            addresses: vec![(validator.encode(), "validator".to_string())],
+        })
+        .await
+        .unwrap();
+    assert!(staking.handle_epoch(3, Epoch(2)).await.is_err());
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/stake/{}/2", validator),
+        Some(Amount::native_whole(1_000)),
+    );
+    staking.handle_epoch(3, Epoch(2)).await.unwrap();
+
+    let voting_powers: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT validator_address, voting_power::TEXT, epoch FROM validator_voting_power_history",
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(voting_powers, vec![(validator.encode(), "1000000000".to_string(), 2)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_epoch_carries_forward_unrefreshed_validators() {
+    let db = common::setup_database("carried_validators").await;
+    let node = MockNode::start();
+
+    // Refreshed on the first epoch only, it falls below capacity on the second one
+    let validator = common::test_address(1);
+    node.respond_abci_query("/vp/pos/validator/addresses/1", HashSet::from([validator.clone()]));
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/stake/{}/1", validator),
+        Some(Amount::native_whole(1_000)),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/state/{}*", validator),
+        Some(ValidatorState::Consensus),
+    );
+    for query in ["commission", "metadata"] {
+        node.respond_abci_query(&format!("/vp/pos/validator/{}/{}*", query, validator), None::<()>);
+    }
+    node.respond_abci_query(
+        "/vp/pos/validator_set/below_capacity/2",
+        BTreeSet::from([WeightedValidator {
+            bonded_stake: Amount::native_whole(1_000),
+            address: validator.clone(),
+        }]),
+    );
+    node.respond_abci_query("/vp/pos/validator_set/*", BTreeSet::<WeightedValidator>::new());
+    node.respond_abci_query("/shell/prefix/*", Vec::<PrefixValue>::new());
+    node.respond_abci_query("/vp/pos/slashes", HashMap::<Address, Vec<Slash>>::new());
+    node.respond_abci_query("/vp/pos/pos_params", PosParams::default());
+
+    let staking = StakingModule::new(node.node(), db.clone());
+    staking.handle_epoch(1, Epoch(1)).await.unwrap();
+    staking.handle_epoch(3, Epoch(2)).await.unwrap();
+
+    let voting_powers: Vec<(String, i64, i64)> = sqlx::query_as(
+        r#"SELECT voting_power::TEXT, epoch, height FROM validator_voting_power_history
+        ORDER BY epoch"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        voting_powers,
+        vec![("1000000000".to_string(), 1, 1), ("1000000000".to_string(), 2, 3)]
+    );
+
+    let statuses: Vec<(i32, i64)> =
+        sqlx::query_as("SELECT status, epoch FROM validator_status_history ORDER BY epoch")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(
+        statuses,
+        vec![
+            (ValidatorState::Consensus as i32, 1),
+            (ValidatorState::BelowCapacity as i32, 2),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn full_refresh_saves_rewards_of_indexed_epochs() {
+    let db = common::setup_database("validator_rewards").await;
+    let node = MockNode::start();
+
+    let validator = common::test_address(1);
+    node.respond_abci_query("/vp/pos/validator/addresses/2", HashSet::from([validator.clone()]));
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/stake/{}/2", validator),
+        Some(Amount::native_whole(1_000)),
+    );
+    for query in ["state", "commission", "metadata"] {
+        node.respond_abci_query(&format!("/vp/pos/validator/{}/{}*", query, validator), None::<()>);
+    }
+    node.respond_abci_query("/vp/pos/slashes", HashMap::<Address, Vec<Slash>>::new());
+    node.respond_abci_query("/vp/pos/pos_params", PosParams::default());
+
+    // The stake of epoch 0 was not indexed, its rewards are skipped. The amount of epoch 1 is
+    // truncated to the smallest unit.
+    let products = validator_rewards_products_handle(&validator);
+    node.respond_abci_query(
+        &format!("/shell/prefix/{}", validator_rewards_product_key(&validator)),
+        vec![
+            prefix_value(products.get_data_key(&Epoch(0)), Dec::new(2, 2).unwrap()),
+            prefix_value(products.get_data_key(&Epoch(1)), Dec::new(1, 2).unwrap()),
+        ],
+    );
+    node.respond_abci_query("/shell/prefix/*", Vec::<PrefixValue>::new());
+    sqlx::query(
+        r#"INSERT INTO validator_voting_power_history (validator_address, voting_power, epoch, height)
+        VALUES ($1, 500000001, 1, 5)"#,
+    )
+    .bind(validator.encode())
+    .execute(&db.pool())
+    .await
+    .unwrap();
+
+    let staking = StakingModule::new(node.node(), db.clone());
+    staking.handle_epoch(10, Epoch(2)).await.unwrap();
+
+    let rewards: Vec<(String, bool, String, i16, i64, i64)> = sqlx::query_as(
+        r#"SELECT validator_address, rewards_product = 0.01, amount::TEXT, denomination,
+            epoch, height
+        FROM validator_reward"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(rewards, vec![(validator.encode(), true, "5000000".to_string(), 6, 1, 10)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn handle_epoch_saves_consensus_keys_of_indexed_epochs() {
+    let db = common::setup_database("consensus_key_epochs").await;
+    let node = MockNode::start();
+
+    // The key rotated at epoch 2, both keys are stored when the epochs are indexed
+    let validator = common::test_address(1);
+    for epoch in [1, 2] {
+        node.respond_abci_query(
+            &format!("/vp/pos/validator/addresses/{}", epoch),
+            HashSet::from([validator.clone()]),
+        );
+        node.respond_abci_query(
+            &format!("/vp/pos/validator/stake/{}/{}", validator, epoch),
+            Some(Amount::native_whole(1_000)),
+        );
+    }
+    for query in ["state", "commission", "metadata"] {
+        node.respond_abci_query(&format!("/vp/pos/validator/{}/{}*", query, validator), None::<()>);
+    }
+    let consensus_keys = validator_consensus_key_handle(&validator).get_data_handler();
+    node.respond_abci_query(
+        &format!("/shell/prefix/{}", validator_consensus_key_key(&validator)),
+        vec![
+            prefix_value(consensus_keys.get_data_key(&Epoch(0)), common::test_public_key(1)),
+            prefix_value(consensus_keys.get_data_key(&Epoch(2)), common::test_public_key(2)),
+        ],
+    );
+    node.respond_abci_query("/shell/prefix/*", Vec::<PrefixValue>::new());
+    node.respond_abci_query("/vp/pos/validator_set/*", BTreeSet::<WeightedValidator>::new());
+    node.respond_abci_query("/vp/pos/slashes", HashMap::<Address, Vec<Slash>>::new());
+    node.respond_abci_query("/vp/pos/pos_params", PosParams::default());
+
+    // Touching the validator refreshes it on the second epoch
+    let staking = StakingModule::new(node.node(), db.clone());
+    staking.handle_epoch(1, Epoch(1)).await.unwrap();
+    staking
+        .handle_message(Message {
+            height: 2,
+            tx_hash: "hash".to_string(),
+            message_type: "tx_change_consensus_key".to_string(),
+            value: json!(validator.encode()),
+            code_hash: None,
+            addresses: vec![(validator.encode(), "validator".to_string())],
+        })
+        .await
+        .unwrap();
+    staking.handle_epoch(3, Epoch(2)).await.unwrap();
+
+    let history: Vec<(String, i64, Option<i64>)> = sqlx::query_as(
+        r#"SELECT consensus_address, from_epoch, to_epoch FROM validator_consensus_key_history
+        ORDER BY from_epoch"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        history,
+        vec![
+            (utils::consensus_address_from_pubkey(common::test_public_key(1).into()), 1, Some(1)),
+            (utils::consensus_address_from_pubkey(common::test_public_key(2).into()), 2, None),
+        ]
+    );
+}
+
+fn prefix_value(key: Key, value: impl BorshSerialize) -> PrefixValue {
+    let mut data = vec![];
+    value.serialize(&mut data).unwrap();
+    PrefixValue { key, value: data }
+}
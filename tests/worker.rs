@@ -0,0 +1,1214 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::str::FromStr;
+
+use ibc_proto::ibc::core::channel::v1::{MsgRecvPacket, Packet};
+use ibc_proto::ibc::core::client::v1::Height;
+use prost::Message as ProstMessage;
+
+use namada_sdk::account::InitAccount;
+use namada_sdk::borsh::BorshSerialize;
+use namada_sdk::governance::storage::proposal::StorageProposal;
+use namada_sdk::governance::storage::vote::ProposalVote as NamadaProposalVote;
+use namada_sdk::governance::{InitProposalData, ProposalType, VoteProposalData};
+use namada_sdk::ibc::storage::ibc_token;
+use namada_sdk::proof_of_stake::storage::validator_consensus_key_handle;
+use namada_sdk::proof_of_stake::storage_key::validator_consensus_key_key;
+use namada_sdk::proof_of_stake::types::{
+    BondDetails, BondId, BondsAndUnbondsDetail, CommissionPair, PosParams, Slash, SlashType,
+    UnbondDetails, ValidatorMetaData, ValidatorState, WeightedValidator,
+};
+use namada_sdk::state::Epoch;
+use namada_sdk::tx::data::pos::{Bond, ClaimRewards, ConsensusKeyChange, MetaDataChange};
+use namada_sdk::tx::data::{Fee, GasLimit, TxType, WrapperTx};
+use namada_sdk::tx::Tx as NamadaTx;
+use namada_sdk::types::address::{self, Address};
+use namada_sdk::types::dec::Dec;
+use namada_sdk::types::hash::Hash;
+use namada_sdk::types::storage::{Key, KeySeg, PrefixValue};
+use namada_sdk::types::token::{
+    balance_key, denom_key, minted_balance_key, Amount, DenominatedAmount, Denomination, Transfer,
+};
+use serde_json::json;
+
+use namadajuno::utils;
+use namadajuno::worker;
+
+mod common;
+use common::{MockNode, RecordedBlock};
+
+const TX_VOTE_PROPOSAL_CODE: &[u8] = b"tx_vote_proposal.wasm";
+const TX_INIT_PROPOSAL_CODE: &[u8] = b"tx_init_proposal.wasm";
+const TX_TRANSFER_CODE: &[u8] = b"tx_transfer.wasm";
+const TX_INIT_ACCOUNT_CODE: &[u8] = b"tx_init_account.wasm";
+const TX_REVEAL_PK_CODE: &[u8] = b"tx_reveal_pk.wasm";
+const TX_BOND_CODE: &[u8] = b"tx_bond.wasm";
+const TX_CLAIM_REWARDS_CODE: &[u8] = b"tx_claim_rewards.wasm";
+const TX_CHANGE_CONSENSUS_KEY_CODE: &[u8] = b"tx_change_consensus_key.wasm";
+const TX_CHANGE_VALIDATOR_METADATA_CODE: &[u8] = b"tx_change_validator_metadata.wasm";
+const TX_IBC_CODE: &[u8] = b"tx_ibc.wasm";
+
+fn checksums() -> HashMap<String, String> {
+    HashMap::from([
+        (
+            common::code_hash(TX_VOTE_PROPOSAL_CODE),
+            "tx_vote_proposal".to_string(),
+        ),
+        (
+            common::code_hash(TX_INIT_PROPOSAL_CODE),
+            "tx_init_proposal".to_string(),
+        ),
+        (
+            common::code_hash(TX_TRANSFER_CODE),
+            "tx_transfer".to_string(),
+        ),
+        (
+            common::code_hash(TX_INIT_ACCOUNT_CODE),
+            "tx_init_account".to_string(),
+        ),
+        (
+            common::code_hash(TX_REVEAL_PK_CODE),
+            "tx_reveal_pk".to_string(),
+        ),
+        (common::code_hash(TX_BOND_CODE), "tx_bond".to_string()),
+        (
+            common::code_hash(TX_CLAIM_REWARDS_CODE),
+            "tx_claim_rewards".to_string(),
+        ),
+        (
+            common::code_hash(TX_CHANGE_CONSENSUS_KEY_CODE),
+            "tx_change_consensus_key".to_string(),
+        ),
+        (
+            common::code_hash(TX_CHANGE_VALIDATOR_METADATA_CODE),
+            "tx_change_validator_metadata".to_string(),
+        ),
+        (common::code_hash(TX_IBC_CODE), "tx_ibc".to_string()),
+    ])
+}
+
+fn tx_allowlist_key() -> Key {
+    Key::from(address::PARAMETERS.to_db_key())
+        .push(&"tx_allowlist".to_owned())
+        .unwrap()
+}
+
+/// Registers the storage queries every block triggers: the epoch lookup, the
+/// PoS validator sets and slashes read by `StakingModule::handle_epoch`, the native token
+/// and total stake read by `SupplyModule::handle_epoch`, the PoS parameters, an empty
+/// tx allowlist and the tx codes, which are all absent from storage.
+fn mock_epoch(node: &MockNode, height: u64, epoch: u64) {
+    node.respond_abci_query(
+        &format!("/shell/epoch_at_height/{}", height),
+        Some(Epoch(epoch)),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/addresses/{}", epoch),
+        HashSet::<Address>::new(),
+    );
+    node.respond_abci_query("/vp/pos/slashes", HashMap::<Address, Vec<Slash>>::new());
+    node.respond_abci_query("/vp/pos/validator_set/*", BTreeSet::<WeightedValidator>::new());
+    node.respond_abci_query("/shell/native_token", address::nam());
+    node.respond_abci_query("/vp/pos/pos_params", PosParams::default());
+    node.respond_abci_query("/vp/pos/total_stake/*", Amount::zero());
+    node.respond_abci_query(&format!("/shell/value/{}", tx_allowlist_key()), Vec::<String>::new());
+    node.respond_abci_query("/shell/value/*", ());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_block_commits_and_validators() {
+    let db = common::setup_database("block").await;
+    let node = MockNode::start();
+    let block = RecordedBlock::load("block_2");
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let (height, num_txs, proposer_address): (i64, i32, String) =
+        sqlx::query_as("SELECT height, num_txs, proposer_address FROM block")
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+    let proposer = block.block["block"]["header"]["proposer_address"]
+        .as_str()
+        .unwrap();
+    assert_eq!(height, 2);
+    assert_eq!(num_txs, 0);
+    assert_eq!(
+        proposer_address,
+        utils::consensus_address(proposer.parse().unwrap())
+    );
+
+    let pre_commits: Vec<(String, i64, i64, String)> =
+        sqlx::query_as("SELECT consensus_address, height, voting_power, flag FROM pre_commit")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(
+        pre_commits,
+        vec![(proposer_address.clone(), 2, 1000, "commit".to_string())]
+    );
+
+    let validators: Vec<(String,)> = sqlx::query_as("SELECT consensus_address FROM validator")
+        .fetch_all(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(validators, vec![(proposer_address,)]);
+
+    let transactions: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transaction")
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(transactions.0, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_absent_signatures_and_uptimes() {
+    let db = common::setup_database("uptime").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    block.block["block"]["last_commit"]["signatures"][0] = json!({
+        "block_id_flag": 1,
+        "validator_address": "",
+        "timestamp": "0001-01-01T00:00:00Z",
+        "signature": null,
+    });
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let proposer = block.block["block"]["header"]["proposer_address"]
+        .as_str()
+        .unwrap();
+    let proposer_address = utils::consensus_address(proposer.parse().unwrap());
+
+    let pre_commits: Vec<(String, String)> =
+        sqlx::query_as("SELECT consensus_address, flag FROM pre_commit")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(
+        pre_commits,
+        vec![(proposer_address.clone(), "absent".to_string())]
+    );
+
+    // Only the block of the window processed so far is indexed
+    let uptime: (String, i64, i64, i64, i64) = sqlx::query_as(
+        "SELECT consensus_address, indexed, signed, missed, height FROM validator_uptime",
+    )
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(uptime, (proposer_address, 1, 0, 1, 2));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_transactions_and_messages() {
+    let db = common::setup_database("transactions").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let voter = common::test_address(7);
+    let raw_tx = common::build_tx(
+        TX_VOTE_PROPOSAL_CODE,
+        VoteProposalData {
+            id: 1,
+            vote: NamadaProposalVote::Yay,
+            voter: voter.clone(),
+            delegations: vec![],
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let tx_hash = utils::tx_hash(raw_tx);
+    let (height, success, tx_type, gas_wanted, gas_used): (i64, bool, String, i64, i64) =
+        sqlx::query_as(
+            "SELECT height, success, tx_type, gas_wanted, gas_used FROM transaction WHERE hash = $1",
+        )
+        .bind(&tx_hash)
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(
+        (height, success, tx_type.as_str(), gas_wanted, gas_used),
+        (2, true, "raw", 1000, 500)
+    );
+
+    let (message_type, value, code_hash): (String, serde_json::Value, Option<String>) =
+        sqlx::query_as(
+            "SELECT type, value, code_hash FROM message WHERE transaction_hash = $1",
+        )
+        .bind(&tx_hash)
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(message_type, "tx_vote_proposal");
+    assert_eq!(value["id"], 1);
+    assert_eq!(code_hash, Some(common::code_hash(TX_VOTE_PROPOSAL_CODE)));
+
+    let addresses: Vec<(String, String)> =
+        sqlx::query_as("SELECT address, role FROM message_address WHERE transaction_hash = $1")
+            .bind(&tx_hash)
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(addresses, vec![(voter.encode(), "voter".to_string())]);
+
+    let votes: Vec<(i32, String, String, i64)> =
+        sqlx::query_as("SELECT proposal_id, voter_address, option, height FROM proposal_vote")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(votes, vec![(1, voter.encode(), "yes".to_string(), 2)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_skips_messages_of_failed_transactions() {
+    let db = common::setup_database("failed_transactions").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let raw_tx = common::build_tx(
+        TX_VOTE_PROPOSAL_CODE,
+        VoteProposalData {
+            id: 1,
+            vote: NamadaProposalVote::Nay,
+            voter: common::test_address(7),
+            delegations: vec![],
+        },
+    );
+    block.push_tx(&raw_tx, 1);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let (success,): (bool,) = sqlx::query_as("SELECT success FROM transaction WHERE hash = $1")
+        .bind(utils::tx_hash(raw_tx))
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert!(!success);
+
+    let messages: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM message")
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(messages.0, 0);
+
+    let votes: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM proposal_vote")
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(votes.0, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_proposals() {
+    let db = common::setup_database("proposals").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let author = common::test_address(7);
+    let raw_tx = common::build_tx(
+        TX_INIT_PROPOSAL_CODE,
+        InitProposalData {
+            id: 0,
+            content: Hash::default(),
+            author: author.clone(),
+            r#type: ProposalType::Default(None),
+            voting_start_epoch: Epoch(3),
+            voting_end_epoch: Epoch(6),
+            grace_epoch: Epoch(9),
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+    node.respond_abci_query(
+        "/vp/governance/proposal/0",
+        Some(StorageProposal {
+            id: 0,
+            content: BTreeMap::from([
+                ("title".to_string(), "Upgrade".to_string()),
+                ("details".to_string(), "Upgrade the chain".to_string()),
+            ]),
+            author: author.clone(),
+            r#type: ProposalType::Default(None),
+            voting_start_epoch: Epoch(3),
+            voting_end_epoch: Epoch(6),
+            grace_epoch: Epoch(9),
+        }),
+    );
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let proposal: (i32, i64, i64, i64, String, String) = sqlx::query_as(
+        "SELECT id, voting_start_epoch, voting_end_epoch, grace_epoch, proposer_address, status FROM proposal",
+    )
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        proposal,
+        (
+            0,
+            3,
+            6,
+            9,
+            author.encode(),
+            "PROPOSAL_STATUS_INIT".to_string()
+        )
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reparse_messages_decodes_unknown_messages() {
+    let db = common::setup_database("reparse").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let voter = common::test_address(7);
+    let raw_tx = common::build_tx(
+        TX_VOTE_PROPOSAL_CODE,
+        VoteProposalData {
+            id: 1,
+            vote: NamadaProposalVote::Abstain,
+            voter: voter.clone(),
+            delegations: vec![],
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    // Index the block before the vote code is known
+    let ctx = common::context(&node, &db, HashMap::new());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let tx_hash = utils::tx_hash(raw_tx);
+    let (message_type,): (String,) =
+        sqlx::query_as("SELECT type FROM message WHERE transaction_hash = $1")
+            .bind(&tx_hash)
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(message_type, "unknown");
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::reparse_messages(&ctx, Some("unknown".to_string()), 1, block.height)
+        .await
+        .unwrap();
+
+    let (message_type,): (String,) =
+        sqlx::query_as("SELECT type FROM message WHERE transaction_hash = $1")
+            .bind(&tx_hash)
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(message_type, "tx_vote_proposal");
+
+    let votes: Vec<(i32, String, String, i64)> =
+        sqlx::query_as("SELECT proposal_id, voter_address, option, height FROM proposal_vote")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(votes, vec![(1, voter.encode(), "abstain".to_string(), 2)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_wrapper_fees() {
+    let db = common::setup_database("wrapper_fees").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let pk = common::test_public_key(7);
+    let mut tx = NamadaTx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+        Fee {
+            amount_per_gas_unit: DenominatedAmount::new(Amount::native_whole(1), Denomination(6)),
+            token: address::nam(),
+        },
+        pk.clone(),
+        Epoch(1),
+        GasLimit::from(20_000),
+        None,
+    ))));
+    tx.add_code(TX_VOTE_PROPOSAL_CODE.to_vec(), None);
+    let raw_tx = tx.to_bytes();
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let fee: (String, String, i16, String, i64, String, String, String) = sqlx::query_as(
+        r#"SELECT tx_type, fee_amount_per_gas_unit::TEXT, fee_denomination, fee_token, gas_limit, fee_payer,
+            fee_payer_pubkey, raw_hash
+        FROM transaction WHERE hash = $1"#,
+    )
+    .bind(utils::tx_hash(raw_tx))
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        fee,
+        (
+            "wrapper".to_string(),
+            "1000000".to_string(),
+            6,
+            address::nam().encode(),
+            20_000,
+            Address::from(&pk).encode(),
+            pk.to_string(),
+            tx.raw_header_hash().to_string(),
+        )
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_transaction_signers() {
+    let db = common::setup_database("transaction_signers").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let pk = common::test_public_key(7);
+    let mut tx = NamadaTx::from_type(TxType::Wrapper(Box::new(WrapperTx::new(
+        Fee {
+            amount_per_gas_unit: DenominatedAmount::new(Amount::native_whole(1), Denomination(6)),
+            token: address::nam(),
+        },
+        pk.clone(),
+        Epoch(1),
+        GasLimit::from(20_000),
+        None,
+    ))));
+    tx.add_code(TX_VOTE_PROPOSAL_CODE.to_vec(), None);
+    tx.sign_wrapper(common::test_secret_key(7));
+    let raw_tx = tx.to_bytes();
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let signers: Vec<(i16, Option<String>, String)> = sqlx::query_as(
+        "SELECT signer_index, public_key, address FROM transaction_signer WHERE transaction_hash = $1",
+    )
+    .bind(utils::tx_hash(raw_tx))
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        signers,
+        vec![(0, Some(pk.to_string()), Address::from(&pk).encode())]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_token_transfers() {
+    let db = common::setup_database("token_transfers").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let source = common::test_address(7);
+    let target = common::test_address(8);
+    let raw_tx = common::build_tx(
+        TX_TRANSFER_CODE,
+        Transfer {
+            source: source.clone(),
+            target: target.clone(),
+            token: address::nam(),
+            amount: DenominatedAmount::new(Amount::native_whole(100), Denomination(6)),
+            key: None,
+            shielded: None,
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let transfers: Vec<(i64, String, String, String, String, i16, bool)> = sqlx::query_as(
+        r#"SELECT height, source, target, token, amount::TEXT, denomination, shielded
+        FROM token_transfer WHERE transaction_hash = $1"#,
+    )
+    .bind(utils::tx_hash(raw_tx))
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        transfers,
+        vec![(
+            2,
+            source.encode(),
+            target.encode(),
+            address::nam().encode(),
+            "100000000".to_string(),
+            6,
+            false,
+        )]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_touched_balances() {
+    let db = common::setup_database("touched_balances").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let source = common::test_address(7);
+    let target = common::test_address(8);
+    let raw_tx = common::build_tx(
+        TX_TRANSFER_CODE,
+        Transfer {
+            source: source.clone(),
+            target: target.clone(),
+            token: address::nam(),
+            amount: DenominatedAmount::new(Amount::native_whole(100), Denomination(6)),
+            key: None,
+            shielded: None,
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    // The source balance is absent from storage once emptied
+    node.respond_abci_query(
+        &format!("/shell/value/{}", balance_key(&address::nam(), &target)),
+        Amount::native_whole(100),
+    );
+    node.respond_abci_query(
+        &format!("/shell/value/{}", denom_key(&address::nam())),
+        Denomination(6),
+    );
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let balances: Vec<(String, String, String, i16, i64)> = sqlx::query_as(
+        r#"SELECT address, token, amount::TEXT, denomination, height
+        FROM account_balance ORDER BY amount"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        balances,
+        vec![
+            (source.encode(), address::nam().encode(), "0".to_string(), 6, 2),
+            (target.encode(), address::nam().encode(), "100000000".to_string(), 6, 2),
+        ]
+    );
+
+    let (history,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM account_balance_history")
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(history, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_balances_of_ibc_receivers() {
+    let db = common::setup_database("ibc_balances").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let receiver = common::test_address(8);
+    let data = json!({
+        "amount": "100",
+        "denom": "uatom",
+        "receiver": receiver.encode(),
+        "sender": "cosmos1sender",
+    });
+    let msg = MsgRecvPacket {
+        packet: Some(Packet {
+            sequence: 1,
+            source_port: "transfer".to_string(),
+            source_channel: "channel-0".to_string(),
+            destination_port: "transfer".to_string(),
+            destination_channel: "channel-1".to_string(),
+            data: data.to_string().into_bytes(),
+            timeout_height: Some(Height {
+                revision_number: 0,
+                revision_height: 1000,
+            }),
+            timeout_timestamp: 0,
+        }),
+        proof_commitment: vec![1; 32],
+        proof_height: Some(Height {
+            revision_number: 0,
+            revision_height: 10,
+        }),
+        signer: "tnam1qqr5jxnyu2xl9g8xr3gnm3mf0qr00qehn5k8wfzq".to_string(),
+    };
+    let raw_tx = common::build_namada_tx_with_raw_data(TX_IBC_CODE, msg.encode_to_vec()).to_bytes();
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+
+    // The received denom is traced through the receiving channel
+    let token = ibc_token("transfer/channel-1/uatom");
+    node.respond_abci_query(
+        &format!("/shell/value/{}", balance_key(&token, &receiver)),
+        Amount::from_u64(100),
+    );
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    // IBC tokens have no denomination in storage, their amounts are in the base unit
+    let balances: Vec<(String, String, String, i16)> =
+        sqlx::query_as("SELECT address, token, amount::TEXT, denomination FROM account_balance")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(balances, vec![(receiver.encode(), token.encode(), "100".to_string(), 0)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_discovers_tx_codes_on_new_epoch() {
+    let db = common::setup_database("tx_code_discovery").await;
+    let node = MockNode::start();
+    let block = RecordedBlock::load("block_2");
+
+    let bond_hash = Hash([1; 32]);
+    let unnamed_hash = Hash([2; 32]);
+    node.respond_block(&block);
+    node.respond_abci_query(&format!("/shell/value/{}", Key::wasm_hash("tx_bond.wasm")), bond_hash);
+    node.respond_abci_query(
+        &format!("/shell/value/{}", tx_allowlist_key()),
+        vec![bond_hash.to_string(), unnamed_hash.to_string()],
+    );
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let tx_codes: Vec<(String, String)> =
+        sqlx::query_as("SELECT code_hash, tx_type FROM tx_code ORDER BY code_hash")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(
+        tx_codes,
+        vec![
+            (hex::encode([1; 32]), "tx_bond".to_string()),
+            (hex::encode([2; 32]), "unknown".to_string()),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_supply_on_new_epoch() {
+    let db = common::setup_database("supply").await;
+    let node = MockNode::start();
+    let block = RecordedBlock::load("block_2");
+
+    node.respond_block(&block);
+    node.respond_abci_query(
+        &format!("/shell/value/{}", minted_balance_key(&address::nam())),
+        Amount::native_whole(1_000),
+    );
+    node.respond_abci_query("/vp/pos/total_stake/*", Amount::native_whole(250));
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let supply: (String, String, String, bool, i64, i64) = sqlx::query_as(
+        r#"SELECT token, total_supply::TEXT, bonded::TEXT, staking_ratio = 0.25, epoch, height
+        FROM supply"#,
+    )
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        supply,
+        (
+            address::nam().encode(),
+            "1000000000".to_string(),
+            "250000000".to_string(),
+            true,
+            1,
+            2,
+        )
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_revealed_public_keys() {
+    let db = common::setup_database("revealed_pks").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let pk = common::test_public_key(7);
+    let raw_tx = common::build_tx(TX_REVEAL_PK_CODE, pk.clone());
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let revealed_pks: Vec<(String, String, String)> =
+        sqlx::query_as("SELECT public_key, address, transaction_hash FROM revealed_pk")
+            .fetch_all(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(
+        revealed_pks,
+        vec![(
+            pk.to_string(),
+            common::test_address(7).encode(),
+            utils::tx_hash(raw_tx)
+        )]
+    );
+
+    let account: (String, Vec<String>, i64) =
+        sqlx::query_as("SELECT type, public_keys, creation_height FROM account WHERE address = $1")
+            .bind(common::test_address(7).encode())
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(account, ("implicit".to_string(), vec![pk.to_string()], 2));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_initialized_accounts() {
+    let db = common::setup_database("initialized_accounts").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let established = "tnam1qyrswpc8qurswpc8qurswpc8qurswpc8qu6szghj";
+    let pk = common::test_public_key(7);
+    let raw_tx = common::build_tx(
+        TX_INIT_ACCOUNT_CODE,
+        InitAccount {
+            public_keys: vec![pk.clone()],
+            vp_code_hash: Hash([1; 32]),
+            threshold: 1,
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    block.block_results["txs_results"][0]["events"] = json!([{
+        "type": "applied",
+        "attributes": [{
+            "key": "inner_tx",
+            "value": json!({ "initialized_accounts": [established] }).to_string(),
+            "index": true,
+        }],
+    }]);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let account: (String, Vec<String>, i16, String) = sqlx::query_as(
+        "SELECT type, public_keys, threshold, vp_code_hash FROM account WHERE address = $1",
+    )
+    .bind(established)
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        account,
+        (
+            "established".to_string(),
+            vec![pk.to_string()],
+            1,
+            hex::encode([1; 32]),
+        )
+    );
+
+    let (role,): (String,) =
+        sqlx::query_as("SELECT role FROM message_address WHERE address = $1")
+            .bind(established)
+            .fetch_one(&db.pool())
+            .await
+            .unwrap();
+    assert_eq!(role, "initialized_account");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_delegations_and_unbondings() {
+    let db = common::setup_database("delegations").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let delegator = common::test_address(7);
+    let validator = common::test_address(1);
+    let raw_tx = common::build_tx(
+        TX_BOND_CODE,
+        Bond {
+            validator: validator.clone(),
+            amount: Amount::native_whole(10),
+            source: Some(delegator.clone()),
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    node.respond_abci_query(
+        "/vp/pos/bonds_and_unbonds/*",
+        HashMap::from([(
+            BondId {
+                source: delegator.clone(),
+                validator: validator.clone(),
+            },
+            BondsAndUnbondsDetail {
+                bonds: vec![BondDetails {
+                    start: Epoch(3),
+                    amount: Amount::native_whole(10),
+                    slashed_amount: None,
+                }],
+                unbonds: vec![UnbondDetails {
+                    start: Epoch(1),
+                    withdraw: Epoch(4),
+                    amount: Amount::native_whole(5),
+                    slashed_amount: None,
+                }],
+                slashes: vec![],
+            },
+        )]),
+    );
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let delegations: Vec<(String, String, String, i64)> = sqlx::query_as(
+        "SELECT delegator_address, validator_address, amount::TEXT, height FROM delegation",
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        delegations,
+        vec![(
+            delegator.encode(),
+            validator.encode(),
+            "10000000".to_string(),
+            2
+        )]
+    );
+
+    let unbondings: Vec<(String, i64, i64)> = sqlx::query_as(
+        "SELECT amount::TEXT, start_epoch, withdrawable_epoch FROM unbonding WHERE delegator_address = $1",
+    )
+    .bind(delegator.encode())
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(unbondings, vec![("5000000".to_string(), 1, 4)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_reward_claims() {
+    let db = common::setup_database("reward_claims").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let delegator = common::test_address(7);
+    let validator = common::test_address(1);
+    let raw_tx = common::build_tx(
+        TX_CLAIM_REWARDS_CODE,
+        ClaimRewards {
+            validator: validator.clone(),
+            source: Some(delegator.clone()),
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    node.respond_abci_query(
+        &format!("/vp/pos/rewards/{}/{}", validator, delegator),
+        Amount::native_whole(3),
+    );
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let claims: Vec<(String, String, String, String, i64)> = sqlx::query_as(
+        r#"SELECT transaction_hash, delegator_address, validator_address, amount::TEXT, height
+        FROM reward_claim"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        claims,
+        vec![(
+            utils::tx_hash(raw_tx),
+            delegator.encode(),
+            validator.encode(),
+            "3000000".to_string(),
+            2
+        )]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_validator_slashes() {
+    let db = common::setup_database("validator_slashes").await;
+    let node = MockNode::start();
+    let block = RecordedBlock::load("block_2");
+
+    let validator = common::test_address(1);
+    let slash = Slash {
+        epoch: Epoch(1),
+        block_height: 1,
+        r#type: SlashType::DuplicateVote,
+        rate: Dec::from_str("0.05").unwrap(),
+    };
+    node.respond_block(&block);
+    node.respond_abci_query(
+        "/vp/pos/slashes",
+        HashMap::from([(validator.clone(), vec![slash])]),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/stake/{}/1", validator),
+        Some(Amount::native_whole(1_000)),
+    );
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let slashes: Vec<(String, bool, i64, i64)> = sqlx::query_as(
+        r#"SELECT validator_address, amount = 50000000, epoch, infraction_height
+        FROM validator_slash"#,
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(slashes, vec![(validator.encode(), true, 1, 1)]);
+
+    let evidences: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM evidence")
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(evidences.0, 0);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_maps_changed_consensus_keys_to_validators() {
+    let db = common::setup_database("consensus_key_change").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let validator = common::test_address(1);
+    let consensus_key = common::test_public_key(9);
+    let raw_tx = common::build_tx(
+        TX_CHANGE_CONSENSUS_KEY_CODE,
+        ConsensusKeyChange {
+            validator: validator.clone(),
+            consensus_key: consensus_key.clone(),
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let validators: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT consensus_address, validator_address FROM validator WHERE validator_address IS NOT NULL",
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        validators,
+        vec![(
+            utils::consensus_address_from_pubkey(consensus_key.into()),
+            Some(validator.encode())
+        )]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_applies_validator_metadata_changes() {
+    let db = common::setup_database("validator_events").await;
+    let node = MockNode::start();
+    let mut block = RecordedBlock::load("block_2");
+
+    let validator = common::test_address(1);
+    let raw_tx = common::build_tx(
+        TX_CHANGE_VALIDATOR_METADATA_CODE,
+        MetaDataChange {
+            validator: validator.clone(),
+            email: None,
+            description: Some("new description".to_string()),
+            website: None,
+            discord_handle: Some("validator#1".to_string()),
+            avatar: Some("https://avatar".to_string()),
+            commission_rate: None,
+        },
+    );
+    block.push_tx(&raw_tx, 0);
+    node.respond_block(&block);
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let events: Vec<(String, String, String, i64)> = sqlx::query_as(
+        "SELECT transaction_hash, validator_address, type, height FROM validator_event",
+    )
+    .fetch_all(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![(
+            utils::tx_hash(raw_tx),
+            validator.encode(),
+            "change_validator_metadata".to_string(),
+            2
+        )]
+    );
+
+    let description: (Option<String>, Option<String>, Option<String>, Option<String>) =
+        sqlx::query_as(
+            r#"SELECT avatar_url, website, details, discord_handle
+            FROM validator_description WHERE validator_address = $1"#,
+        )
+        .bind(validator.encode())
+        .fetch_one(&db.pool())
+        .await
+        .unwrap();
+    assert_eq!(
+        description,
+        (
+            Some("https://avatar".to_string()),
+            None,
+            Some("new description".to_string()),
+            Some("validator#1".to_string())
+        )
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn process_block_saves_consensus_validators_on_new_epoch() {
+    let db = common::setup_database("consensus_validators").await;
+    let node = MockNode::start();
+    let block = RecordedBlock::load("block_2");
+
+    let validator = common::test_address(1);
+    node.respond_block(&block);
+    node.respond_abci_query("/vp/pos/validator/addresses/1", HashSet::from([validator.clone()]));
+    node.respond_abci_query(
+        "/vp/pos/validator_set/consensus/1",
+        BTreeSet::from([WeightedValidator {
+            bonded_stake: Amount::native_whole(1_000),
+            address: validator.clone(),
+        }]),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/state/{}*", validator),
+        Some(ValidatorState::Consensus),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/stake/{}/1", validator),
+        Some(Amount::native_whole(1_000)),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/commission/{}*", validator),
+        Some(CommissionPair {
+            commission_rate: Dec::new(5, 2).unwrap(),
+            max_commission_change_per_epoch: Dec::new(1, 2).unwrap(),
+        }),
+    );
+    node.respond_abci_query(
+        &format!("/vp/pos/validator/metadata/{}*", validator),
+        Some(ValidatorMetaData {
+            email: "validator@namada.net".to_string(),
+            description: Some("A validator".to_string()),
+            website: Some("https://namada.net".to_string()),
+            discord_handle: None,
+            avatar: None,
+        }),
+    );
+    let consensus_keys = validator_consensus_key_handle(&validator).get_data_handler();
+    let mut consensus_key = vec![];
+    common::test_public_key(2).serialize(&mut consensus_key).unwrap();
+    node.respond_abci_query(
+        &format!("/shell/prefix/{}", validator_consensus_key_key(&validator)),
+        vec![PrefixValue { key: consensus_keys.get_data_key(&Epoch(0)), value: consensus_key }],
+    );
+    node.respond_abci_query("/shell/prefix/*", Vec::<PrefixValue>::new());
+    mock_epoch(&node, block.height, 1);
+
+    let ctx = common::context(&node, &db, checksums());
+    worker::process_block(&ctx, block.height).await.unwrap();
+
+    let address = validator.encode();
+    // The validator info is keyed by the Namada address despite its column name
+    let (max_change_rate,): (bool,) = sqlx::query_as(
+        "SELECT max_change_rate = 0.01 FROM validator_info WHERE consensus_address = $1",
+    )
+    .bind(&address)
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert!(max_change_rate);
+
+    let voting_power: (String, i64) = sqlx::query_as(
+        "SELECT voting_power::TEXT, height FROM validator_voting_power WHERE validator_address = $1",
+    )
+    .bind(&address)
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(voting_power, ("1000000000".to_string(), 2));
+
+    let (commission,): (bool,) = sqlx::query_as(
+        "SELECT commission = 0.05 FROM validator_commission WHERE validator_address = $1",
+    )
+    .bind(&address)
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert!(commission);
+
+    let status: (i32, bool) = sqlx::query_as(
+        "SELECT status, jailed FROM validator_status WHERE validator_address = $1",
+    )
+    .bind(&address)
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(status, (ValidatorState::Consensus as i32, false));
+
+    let description: (Option<String>, Option<String>, Option<String>) = sqlx::query_as(
+        "SELECT email, details, website FROM validator_description WHERE validator_address = $1",
+    )
+    .bind(&address)
+    .fetch_one(&db.pool())
+    .await
+    .unwrap();
+    assert_eq!(
+        description,
+        (
+            Some("validator@namada.net".to_string()),
+            Some("A validator".to_string()),
+            Some("https://namada.net".to_string())
+        )
+    );
+}